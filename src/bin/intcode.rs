@@ -0,0 +1,15 @@
+use std::env;
+use std::process;
+
+use advent_2029::computer::dump::cli;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match cli(&args) {
+        Ok(output) => print!("{output}"),
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    }
+}
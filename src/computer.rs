@@ -1,16 +1,104 @@
+use std::collections::{BTreeMap, VecDeque};
 use std::ops::ControlFlow;
-
-pub type Memory = Vec<i32>;
+use std::str::FromStr;
+
+pub mod batch;
+pub mod builder;
+pub mod cfg;
+pub mod classify;
+pub mod custom;
+pub mod decompile;
+pub mod device;
+pub mod diagnostics;
+pub mod disasm;
+pub mod diverge;
+pub mod dump;
+pub mod equivalence;
+mod error;
+pub mod gas;
+pub mod lint;
+pub mod loops;
+pub mod memory;
+pub mod sandbox;
+pub mod scheduler;
+pub mod specialize;
+pub mod symbolic;
+pub mod testcase;
+pub mod transcript;
+pub mod transpile;
+
+use custom::CustomOp;
+use device::Mapping;
+pub use error::ComputerError;
+use gas::Gas;
+use loops::LoopDetector;
+pub use memory::Memory;
+use sandbox::Sandbox;
 
 #[derive(Debug, Clone)]
 pub struct Computer {
     pub pointer: usize,
     pub memory: Memory,
+    /// Fallback for `Input` once `inputs` is empty.
     pub input: Option<i32>,
+    /// Values consumed in order by `Input`.
+    pub inputs: VecDeque<i32>,
     pub output: Vec<i32>,
+    /// Pointer of the `Output` instruction that produced each output.
+    pub output_pointers: Vec<usize>,
+    pub arithmetic: Arithmetic,
+    pub validation: Validation,
+    /// Extra instructions added with `register_op`, by opcode.
+    pub custom_ops: BTreeMap<u32, CustomOp>,
+    pub isa: Isa,
+    pub relative_base: i32,
+    /// Set by `detect_loops`.
+    pub loop_detector: Option<LoopDetector>,
+    /// Address ranges served by devices instead of memory.
+    pub devices: Vec<Mapping>,
+    /// Set by `meter`.
+    pub gas: Option<Gas>,
+    /// Limits on memory growth. Unlimited by default.
+    pub sandbox: Sandbox,
+}
+
+/// The instruction set a program was written for. Defaults to `Day5`, so
+/// addresses past the end of memory fail unless `Day9` is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Isa {
+    /// `Add`, `Mul` and `Exit` in position mode.
+    Day2,
+    /// Adds I/O, jumps, comparisons and immediate mode.
+    #[default]
+    Day5,
+    /// Adds relative mode, `AdjustBase` and memory past the end of the
+    /// program, which reads as zero. Cells are still `i32`, so programs with
+    /// larger values, like the real day 9 input, can't be loaded.
+    Day9,
+}
+
+/// How `Add`, `Mul` and `AdjustBase` handle results that don't fit in a
+/// memory cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arithmetic {
+    /// Stop with `ComputerError::Overflow`.
+    #[default]
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+/// How strictly instructions are checked before running them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Validation {
+    /// Immediate-mode destinations write to the parameter's own cell.
+    #[default]
+    Permissive,
+    /// Immediate-mode destinations stop with `ComputerError::ImmediateWrite`.
+    Strict,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OpCode {
     Add = 1,
     Mul = 2,
@@ -20,16 +108,18 @@ pub enum OpCode {
     JumpFalse = 6,
     LessThan = 7,
     Equal = 8,
+    AdjustBase = 9,
     Exit = 99,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParameterMode {
     Position = 0,
     Immediate = 1,
+    Relative = 2,
 }
 
-type Modes = [ParameterMode; 3];
+pub type Modes = [ParameterMode; 3];
 type Command = (OpCode, Modes);
 
 impl Computer {
@@ -37,12 +127,23 @@ impl Computer {
         Self::new_with(vec![])
     }
 
-    pub fn new_with(memory: Memory) -> Self {
+    pub fn new_with<M: Into<Memory>>(memory: M) -> Self {
         Self {
             pointer: 0,
-            memory,
+            memory: memory.into(),
             input: None,
+            inputs: VecDeque::new(),
             output: vec![],
+            output_pointers: vec![],
+            arithmetic: Arithmetic::default(),
+            validation: Validation::default(),
+            custom_ops: BTreeMap::new(),
+            isa: Isa::default(),
+            relative_base: 0,
+            loop_detector: None,
+            devices: vec![],
+            gas: None,
+            sandbox: Sandbox::default(),
         }
     }
 
@@ -54,6 +155,11 @@ impl Computer {
         self.len() == 0
     }
 
+    /// True when the instruction at the pointer is `Input`.
+    pub fn at_input(&self) -> bool {
+        matches!(self.memory.get(self.pointer), Some(value) if value % 100 == 3)
+    }
+
     pub fn read_command(&self) -> Command {
         let command = self.memory[self.pointer] as u32;
         let op = (command.digit(1) * 10) + command.digit(0);
@@ -64,173 +170,305 @@ impl Computer {
         (OpCode::from(op), [mode_1, mode_2, mode_3])
     }
 
-    pub fn run_command(&mut self) -> ControlFlow<Result<String, String>, i32> {
-        let (op, modes) = self.read_command();
+    /// Decodes the instruction at the pointer, rejecting opcodes and modes
+    /// outside of `self.isa`.
+    pub fn decode_command(&self) -> Result<Command, ComputerError> {
+        let value = *self
+            .memory
+            .get(self.pointer)
+            .ok_or_else(|| self.out_of_bounds(self.pointer))?;
+        let unknown = ComputerError::UnknownOpCode {
+            pointer: self.pointer,
+            value,
+        };
+        if value < 0 {
+            return Err(unknown);
+        }
+        let command = value as u32;
+        let op = OpCode::decode((command.digit(1) * 10) + command.digit(0))
+            .filter(|op| self.isa.supports(op))
+            .ok_or(unknown)?;
+
+        let mut modes = [
+            ParameterMode::Position,
+            ParameterMode::Position,
+            ParameterMode::Position,
+        ];
+        for (param, mode) in modes.iter_mut().enumerate().take(op.arity()) {
+            *mode = ParameterMode::decode(command.digit(param as u32 + 2))
+                .filter(|mode| self.isa.supports_mode(mode))
+                .ok_or(ComputerError::InvalidMode {
+                    pointer: self.pointer,
+                    value,
+                })?;
+        }
+        Ok((op, modes))
+    }
+
+    pub fn run_command(&mut self) -> ControlFlow<Result<String, ComputerError>, i32> {
+        for mapping in &mut self.devices {
+            mapping.device.tick();
+        }
+        // Negative values fail in `decode_command` rather than wrapping onto
+        // a custom opcode.
+        let code = self
+            .memory
+            .get(self.pointer)
+            .and_then(|&value| u32::try_from(value).ok())
+            .map(|value| value % 100);
+        if let Some(custom) = code.and_then(|code| self.custom_ops.get(&code)).cloned() {
+            if let Err(err) = self.charge(None) {
+                return ControlFlow::Break(Err(err));
+            }
+            return self.run_custom(&custom);
+        }
+        let (op, modes) = match self.decode_command() {
+            Ok(command) => command,
+            Err(err) => return ControlFlow::Break(Err(err)),
+        };
+        if let Err(err) = self.charge(Some(&op)) {
+            return ControlFlow::Break(Err(err));
+        }
         // println!("Running command at {} = {op:?}", self.pointer);
         match op {
             OpCode::Add => match self.add(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::Mul => match self.mul(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::Input => match self.input(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::Output => match self.output(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::JumpTrue => match self.jump_true(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::JumpFalse => match self.jump_false(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::LessThan => match self.jump_less_than(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::Equal => match self.jump_equal(modes) {
                 Ok(res) => ControlFlow::Continue(res),
-                Err(msg) => ControlFlow::Break(Err(msg)),
+                Err(err) => ControlFlow::Break(Err(err)),
+            },
+            OpCode::AdjustBase => match self.adjust_base(modes) {
+                Ok(res) => ControlFlow::Continue(res),
+                Err(err) => ControlFlow::Break(Err(err)),
             },
             OpCode::Exit => ControlFlow::Break(Ok("exit 99".to_string())),
             // _ => unimplemented!(),
         }
     }
 
-    pub fn run_program(&mut self) -> Result<i32, String> {
+    /// Runs until the program halts. Without a gas budget from `meter`, runs
+    /// are cut off after `MAX_ITER` instructions.
+    pub fn run_program(&mut self) -> Result<i32, ComputerError> {
         const MAX_ITER: u32 = 1000;
-        let err_msg = format!("Hit max iterations {MAX_ITER}");
         let mut i = 0;
         loop {
             i += 1;
-            if i > MAX_ITER {
-                return Err(err_msg);
+            if i > MAX_ITER && self.gas.is_none() {
+                return Err(ComputerError::MaxIterations(MAX_ITER));
             }
 
+            let from = self.pointer;
             if let ControlFlow::Break(res) = self.run_command() {
-                if let Err(msg) = res {
-                    eprintln!("Program error: {msg}");
-                    return Err(msg);
+                if let Err(err) = res {
+                    eprintln!("Program error: {err}");
+                    return Err(err);
                 }
                 break;
             }
+            self.check_loop(from)?;
         }
         Ok(self.memory[0])
     }
 
-    pub fn run_from(program: &str, input: Option<i32>) -> Result<i32, String> {
+    pub fn run_from(program: &str, input: Option<i32>) -> Result<i32, ComputerError> {
         let mut computer = Computer::from(program);
         computer.input = input;
         computer.run_program()?;
         computer
             .check_diagnostics()
-            .or(Err("Error running program".to_string()))
+            .map_err(|diagnostics| ComputerError::DiagnosticsFailed(diagnostics.to_vec()))
     }
 
-    pub fn set_parameters(&mut self, (noun, verb): (i32, i32)) -> Result<(), String> {
-        if self.len() >= 2 {
+    pub fn set_parameters(&mut self, (noun, verb): (i32, i32)) -> Result<(), ComputerError> {
+        if self.len() > 2 {
             self.memory[1] = noun;
             self.memory[2] = verb;
             Ok(())
         } else {
-            let msg = format!("Not enough memory to set parameters {:?}", &self.memory);
-            Err(msg)
+            Err(ComputerError::NotEnoughMemory { size: self.len() })
         }
     }
 
-    fn get_pos(&self, pos: usize, mode: &ParameterMode) -> usize {
-        match mode {
-            ParameterMode::Immediate => pos,
-            ParameterMode::Position => self.memory[pos] as usize,
+    /// Makes sure `address` is in memory, growing it if the ISA allows.
+    fn ensure_memory(&mut self, address: usize) -> Result<(), ComputerError> {
+        self.sandbox.check_address(self.pointer, address)?;
+        if address < self.len() || self.mapping(address).is_some() {
+            Ok(())
+        } else if self.isa.grows_memory() {
+            self.sandbox.check_cells(self.pointer, address + 1)?;
+            self.memory.resize(address + 1, 0);
+            Ok(())
+        } else {
+            Err(self.out_of_bounds(address))
         }
     }
 
-    fn get_pos_3(&self, modes: Modes) -> Result<(usize, usize, usize), String> {
-        let a = self.get_pos(self.pointer + 1, &modes[0]);
-        let b = self.get_pos(self.pointer + 2, &modes[1]);
-        let to = self.get_pos(self.pointer + 3, &modes[2]);
+    /// Resolves the parameter at `offset` from the pointer to an address in
+    /// memory.
+    fn get_pos(&mut self, offset: usize, mode: &ParameterMode) -> Result<usize, ComputerError> {
+        let pos = self.pointer + offset;
+        self.ensure_memory(pos)?;
+        // Parameters are part of the instruction, so they can't come from a
+        // device mapped past the end of memory.
+        let param = *self
+            .memory
+            .get(pos)
+            .ok_or_else(|| self.out_of_bounds(pos))?;
+        let address = match mode {
+            ParameterMode::Immediate => return Ok(pos),
+            ParameterMode::Position => param as i64,
+            ParameterMode::Relative => self.relative_base as i64 + param as i64,
+        };
+        let address = usize::try_from(address).map_err(|_| ComputerError::NegativeAddress {
+            pointer: self.pointer,
+            address,
+        })?;
+        self.ensure_memory(address)?;
+        Ok(address)
+    }
 
-        let mem_size = self.memory.len();
-        if a >= mem_size || b >= mem_size || to >= mem_size {
-            let err_msg =
-                format!("Index out of bounds, memory size={mem_size}, a={a}, b={b}, to={to}.");
-            return Err(err_msg);
+    fn out_of_bounds(&self, address: usize) -> ComputerError {
+        ComputerError::OutOfBounds {
+            pointer: self.pointer,
+            address,
+            size: self.len(),
         }
+    }
+
+    /// Resolves the destination parameter at `offset` from the pointer.
+    fn get_write_pos(
+        &mut self,
+        offset: usize,
+        mode: &ParameterMode,
+    ) -> Result<usize, ComputerError> {
+        if self.validation == Validation::Strict && *mode == ParameterMode::Immediate {
+            return Err(ComputerError::ImmediateWrite {
+                pointer: self.pointer,
+                op: self.read_command().0,
+                param: offset,
+            });
+        }
+        self.get_pos(offset, mode)
+    }
+
+    fn get_pos_3(&mut self, modes: Modes) -> Result<(usize, usize, usize), ComputerError> {
+        let a = self.get_pos(1, &modes[0])?;
+        let b = self.get_pos(2, &modes[1])?;
+        let to = self.get_write_pos(3, &modes[2])?;
 
         Ok((a, b, to))
     }
 
-    fn add(&mut self, modes: Modes) -> Result<i32, String> {
+    /// Applies `op` to `a` and `b` according to `self.arithmetic`.
+    /// `AdjustBase` adds like `Add`.
+    fn arithmetic(&self, op: OpCode, a: i32, b: i32) -> Result<i32, ComputerError> {
+        let is_add = op != OpCode::Mul;
+        match self.arithmetic {
+            Arithmetic::Checked => {
+                let res = if is_add {
+                    a.checked_add(b)
+                } else {
+                    a.checked_mul(b)
+                };
+                res.ok_or(ComputerError::Overflow {
+                    pointer: self.pointer,
+                    op,
+                    a,
+                    b,
+                })
+            }
+            Arithmetic::Wrapping if is_add => Ok(a.wrapping_add(b)),
+            Arithmetic::Wrapping => Ok(a.wrapping_mul(b)),
+            Arithmetic::Saturating if is_add => Ok(a.saturating_add(b)),
+            Arithmetic::Saturating => Ok(a.saturating_mul(b)),
+        }
+    }
+
+    fn add(&mut self, modes: Modes) -> Result<i32, ComputerError> {
         let (a, b, to) = self.get_pos_3(modes)?;
 
-        self.memory[to] = self.memory[a] + self.memory[b];
+        let (a, b) = (self.read(a), self.read(b));
+        let res = self.arithmetic(OpCode::Add, a, b)?;
+        self.write(to, res);
         self.pointer += 4;
-        Ok(self.memory[to])
+        Ok(res)
     }
 
-    fn mul(&mut self, modes: Modes) -> Result<i32, String> {
+    fn mul(&mut self, modes: Modes) -> Result<i32, ComputerError> {
         let (a, b, to) = self.get_pos_3(modes)?;
 
-        self.memory[to] = self.memory[a] * self.memory[b];
+        let (a, b) = (self.read(a), self.read(b));
+        let res = self.arithmetic(OpCode::Mul, a, b)?;
+        self.write(to, res);
         self.pointer += 4;
-        Ok(self.memory[to])
-    }
-
-    fn input(&mut self, modes: Modes) -> Result<i32, String> {
-        let address = self.get_pos(self.pointer + 1, &modes[0]);
-        if self.len() > address {
-            if let Some(input) = self.input {
-                self.memory[address] = input;
-                self.pointer += 2;
-                Ok(address as i32)
-            } else {
-                Err("Input is None".to_string())
-            }
-        } else {
-            let msg = format!("Address {address} not in memory");
-            Err(msg)
-        }
+        Ok(res)
     }
 
-    fn output(&mut self, modes: Modes) -> Result<i32, String> {
-        let address = self.get_pos(self.pointer + 1, &modes[0]);
-        if self.len() > address {
-            let res = self.memory[address];
-            self.output.push(res);
+    fn input(&mut self, modes: Modes) -> Result<i32, ComputerError> {
+        let address = self.get_write_pos(1, &modes[0])?;
+        if let Some(input) = self.inputs.pop_front().or(self.input) {
+            self.write(address, input);
             self.pointer += 2;
-            Ok(res)
+            Ok(address as i32)
         } else {
-            let msg = format!("Address {address} not in memory");
-            Err(msg)
+            Err(ComputerError::MissingInput {
+                pointer: self.pointer,
+            })
         }
     }
 
+    fn output(&mut self, modes: Modes) -> Result<i32, ComputerError> {
+        let address = self.get_pos(1, &modes[0])?;
+        let res = self.read(address);
+        self.output.push(res);
+        self.output_pointers.push(self.pointer);
+        self.pointer += 2;
+        Ok(res)
+    }
+
     pub fn check_diagnostics(&self) -> Result<i32, &[i32]> {
-        let len = self.output.len();
-        let diagnostics = &self.output[0..(len - 1)];
-        if diagnostics.iter().any(|&diag| diag != 0) {
-            eprintln!("Diagnostics failed! Output: {:?}", &diagnostics);
-            Err(diagnostics)
-        } else {
-            let res = self.output.last().unwrap();
-            Ok(*res)
+        match self.output.split_last() {
+            Some((&res, diagnostics)) if diagnostics.iter().all(|&diag| diag == 0) => Ok(res),
+            Some((_, diagnostics)) => {
+                eprintln!("Diagnostics failed! Output: {:?}", &diagnostics);
+                Err(diagnostics)
+            }
+            None => Err(&self.output),
         }
     }
 
-    fn jump_if(&mut self, modes: Modes, cmp: fn(i32) -> bool) -> Result<i32, String> {
-        let a = self.get_pos(self.pointer + 1, &modes[0]);
-        let a_val = self.memory[a];
+    fn jump_if(&mut self, modes: Modes, cmp: fn(i32) -> bool) -> Result<i32, ComputerError> {
+        let a = self.get_pos(1, &modes[0])?;
+        let a_val = self.read(a);
         if cmp(a_val) {
-            let b = self.get_pos(self.pointer + 2, &modes[1]);
-            self.pointer = self.memory[b] as usize;
+            let b = self.get_pos(2, &modes[1])?;
+            self.pointer = self.read(b) as usize;
             Ok(a_val)
         } else {
             self.pointer += 3;
@@ -238,45 +476,53 @@ impl Computer {
         }
     }
 
-    fn jump_true(&mut self, modes: Modes) -> Result<i32, String> {
+    fn jump_true(&mut self, modes: Modes) -> Result<i32, ComputerError> {
         self.jump_if(modes, |val| val != 0)
     }
 
-    fn jump_false(&mut self, modes: Modes) -> Result<i32, String> {
+    fn jump_false(&mut self, modes: Modes) -> Result<i32, ComputerError> {
         self.jump_if(modes, |val| val == 0)
     }
 
-    fn jump_less_than(&mut self, modes: Modes) -> Result<i32, String> {
-        let a = self.get_pos(self.pointer + 1, &modes[0]);
-        let b = self.get_pos(self.pointer + 2, &modes[1]);
-        let to = self.get_pos(self.pointer + 3, &modes[2]);
+    fn jump_less_than(&mut self, modes: Modes) -> Result<i32, ComputerError> {
+        let a = self.get_pos(1, &modes[0])?;
+        let b = self.get_pos(2, &modes[1])?;
+        let to = self.get_write_pos(3, &modes[2])?;
 
-        let a_val = self.memory[a];
-        let b_val = self.memory[b];
+        let a_val = self.read(a);
+        let b_val = self.read(b);
 
         let res = if a_val < b_val { 1 } else { 0 };
 
-        self.memory[to] = res;
+        self.write(to, res);
         self.pointer += 4;
 
         Ok(res)
     }
 
-    fn jump_equal(&mut self, modes: Modes) -> Result<i32, String> {
-        let a = self.get_pos(self.pointer + 1, &modes[0]);
-        let b = self.get_pos(self.pointer + 2, &modes[1]);
-        let to = self.get_pos(self.pointer + 3, &modes[2]);
+    fn jump_equal(&mut self, modes: Modes) -> Result<i32, ComputerError> {
+        let a = self.get_pos(1, &modes[0])?;
+        let b = self.get_pos(2, &modes[1])?;
+        let to = self.get_write_pos(3, &modes[2])?;
 
-        let a_val = self.memory[a];
-        let b_val = self.memory[b];
+        let a_val = self.read(a);
+        let b_val = self.read(b);
 
         let res = if a_val == b_val { 1 } else { 0 };
 
-        self.memory[to] = res;
+        self.write(to, res);
         self.pointer += 4;
 
         Ok(res)
     }
+
+    fn adjust_base(&mut self, modes: Modes) -> Result<i32, ComputerError> {
+        let a = self.get_pos(1, &modes[0])?;
+        let a = self.read(a);
+        self.relative_base = self.arithmetic(OpCode::AdjustBase, self.relative_base, a)?;
+        self.pointer += 2;
+        Ok(self.relative_base)
+    }
 }
 
 impl Default for Computer {
@@ -285,36 +531,116 @@ impl Default for Computer {
     }
 }
 
+impl FromStr for Computer {
+    type Err = ComputerError;
+
+    /// Parses a comma-separated program, failing on cells that don't fit in
+    /// an `i32`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let memory = input
+            .split(',')
+            .enumerate()
+            .map(|(address, value)| {
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| ComputerError::InvalidCell {
+                        address,
+                        value: value.trim().to_string(),
+                    })
+            })
+            .collect::<Result<Memory, _>>()?;
+        Ok(Self::new_with(memory))
+    }
+}
+
 impl From<&str> for Computer {
+    /// Panics on cells that don't fit in an `i32`; use `str::parse` to get a
+    /// `ComputerError::InvalidCell` instead.
     fn from(input: &str) -> Self {
-        let memory = input.split(',').map(|num| num.parse().unwrap()).collect();
-        Self::new_with(memory)
+        input.parse().unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl OpCode {
+    pub fn decode(input: u32) -> Option<Self> {
+        match input {
+            1 => Some(OpCode::Add),
+            2 => Some(OpCode::Mul),
+            3 => Some(OpCode::Input),
+            4 => Some(OpCode::Output),
+            5 => Some(OpCode::JumpTrue),
+            6 => Some(OpCode::JumpFalse),
+            7 => Some(OpCode::LessThan),
+            8 => Some(OpCode::Equal),
+            9 => Some(OpCode::AdjustBase),
+            99 => Some(OpCode::Exit),
+            _ => None,
+        }
+    }
+
+    /// Number of parameters following the opcode in memory.
+    pub fn arity(&self) -> usize {
+        match self {
+            OpCode::Add | OpCode::Mul | OpCode::LessThan | OpCode::Equal => 3,
+            OpCode::JumpTrue | OpCode::JumpFalse => 2,
+            OpCode::Input | OpCode::Output | OpCode::AdjustBase => 1,
+            OpCode::Exit => 0,
+        }
+    }
+
+    pub fn is_jump(&self) -> bool {
+        matches!(self, OpCode::JumpTrue | OpCode::JumpFalse)
+    }
+}
+
+impl Isa {
+    pub fn supports(&self, op: &OpCode) -> bool {
+        match self {
+            Isa::Day2 => matches!(op, OpCode::Add | OpCode::Mul | OpCode::Exit),
+            Isa::Day5 => *op != OpCode::AdjustBase,
+            Isa::Day9 => true,
+        }
+    }
+
+    pub fn supports_mode(&self, mode: &ParameterMode) -> bool {
+        match self {
+            Isa::Day2 => *mode == ParameterMode::Position,
+            Isa::Day5 => *mode != ParameterMode::Relative,
+            Isa::Day9 => true,
+        }
+    }
+
+    pub fn grows_memory(&self) -> bool {
+        *self == Isa::Day9
     }
 }
 
 impl From<u32> for OpCode {
     fn from(input: u32) -> Self {
+        match OpCode::decode(input) {
+            Some(op) => op,
+            None => unimplemented!(),
+        }
+    }
+}
+
+impl ParameterMode {
+    pub fn decode(input: u32) -> Option<Self> {
         match input {
-            1 => OpCode::Add,
-            2 => OpCode::Mul,
-            3 => OpCode::Input,
-            4 => OpCode::Output,
-            5 => OpCode::JumpTrue,
-            6 => OpCode::JumpFalse,
-            7 => OpCode::LessThan,
-            8 => OpCode::Equal,
-            99 => OpCode::Exit,
-            _ => unimplemented!(),
+            0 => Some(ParameterMode::Position),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
         }
     }
 }
 
 impl From<u32> for ParameterMode {
     fn from(input: u32) -> Self {
-        match input {
-            0 => ParameterMode::Position,
-            1 => ParameterMode::Immediate,
-            _ => unimplemented!(),
+        match ParameterMode::decode(input) {
+            Some(mode) => mode,
+            None => unimplemented!(),
         }
     }
 }
@@ -333,6 +659,149 @@ impl Digits<u32> for u32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_isa() {
+        let mut computer = Computer::from("1,0,0,0,99");
+        computer.isa = Isa::Day2;
+        assert_eq!(computer.run_program(), Ok(2));
+
+        let mut computer = Computer::from("1002,4,3,4,33");
+        computer.isa = Isa::Day2;
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::InvalidMode {
+                pointer: 0,
+                value: 1002
+            })
+        );
+
+        let mut computer = Computer::from("3,0,4,0,99");
+        computer.isa = Isa::Day2;
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::UnknownOpCode {
+                pointer: 0,
+                value: 3
+            })
+        );
+
+        let mut computer = Computer::from("109,1,204,-1,99");
+        computer.isa = Isa::Day5;
+        assert!(matches!(
+            computer.run_program(),
+            Err(ComputerError::UnknownOpCode { value: 109, .. })
+        ));
+    }
+
+    #[test]
+    fn test_relative() {
+        // quine from day 9
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let mut computer = Computer::from(quine);
+        computer.isa = Isa::Day9;
+        computer.run_program().expect("Program failed");
+        assert_eq!(computer.output, Computer::from(quine).memory);
+
+        // reading past the end of the program
+        let mut computer = Computer::from("4,10,99");
+        computer.isa = Isa::Day9;
+        assert_eq!(computer.run_program(), Ok(4));
+        assert_eq!(computer.output, vec![0]);
+        assert_eq!(computer.len(), 11);
+
+        // the default ISA doesn't grow memory
+        let mut computer = Computer::from("4,1000000,99");
+        assert!(matches!(
+            computer.run_program(),
+            Err(ComputerError::OutOfBounds { .. })
+        ));
+        assert_eq!(computer.len(), 3);
+
+        let mut computer = Computer::from("204,-1,99");
+        computer.isa = Isa::Day9;
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::NegativeAddress {
+                pointer: 0,
+                address: -1
+            })
+        );
+
+        let mut computer = Computer::from("109,2147483647,109,1,99");
+        computer.isa = Isa::Day9;
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::Overflow {
+                pointer: 2,
+                op: OpCode::AdjustBase,
+                a: i32::MAX,
+                b: 1
+            })
+        );
+
+        // cells are i32, so the real day 9 input doesn't load
+        assert_eq!(
+            "104,1125899906842624,99".parse::<Computer>().err(),
+            Some(ComputerError::InvalidCell {
+                address: 1,
+                value: "1125899906842624".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let program = "11101,2,3,3,99";
+
+        let mut computer = Computer::from(program);
+        assert_eq!(computer.run_program(), Ok(11101));
+        assert_eq!(computer.memory, vec![11101, 2, 3, 5, 99]);
+
+        let mut computer = Computer::from(program);
+        computer.validation = Validation::Strict;
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::ImmediateWrite {
+                pointer: 0,
+                op: OpCode::Add,
+                param: 3
+            })
+        );
+
+        let mut computer = Computer::from("103,0,99");
+        computer.input = Some(1);
+        computer.validation = Validation::Strict;
+        assert!(matches!(
+            computer.run_program(),
+            Err(ComputerError::ImmediateWrite { param: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // i32::MAX * 2
+        let program = "1002,5,2,0,99,2147483647";
+
+        let mut computer = Computer::from(program);
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::Overflow {
+                pointer: 0,
+                op: OpCode::Mul,
+                a: i32::MAX,
+                b: 2
+            })
+        );
+
+        let mut computer = Computer::from(program);
+        computer.arithmetic = Arithmetic::Wrapping;
+        assert_eq!(computer.run_program(), Ok(-2));
+
+        let mut computer = Computer::from(program);
+        computer.arithmetic = Arithmetic::Saturating;
+        assert_eq!(computer.run_program(), Ok(i32::MAX));
+    }
+
     #[test]
     fn test_digit() {
         assert_eq!(1002.digit(0), 2);
@@ -0,0 +1,212 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use super::{Computer, ComputerError};
+
+/// Changes applied to the base program before running it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variant {
+    /// `(address, value)` pairs written to memory.
+    pub patches: Vec<(usize, i32)>,
+    pub input: Option<i32>,
+}
+
+impl Variant {
+    pub fn patch(patches: &[(usize, i32)]) -> Self {
+        Self {
+            patches: patches.to_vec(),
+            input: None,
+        }
+    }
+
+    pub fn input(input: i32) -> Self {
+        Self {
+            patches: vec![],
+            input: Some(input),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// Position of the variant in the input iterator.
+    pub index: usize,
+    pub variant: Variant,
+    pub result: Result<i32, ComputerError>,
+    pub computer: Computer,
+}
+
+/// Runs many variants of one program on a pool of threads.
+#[derive(Debug, Clone)]
+pub struct Batch<'a> {
+    base: &'a Computer,
+    threads: usize,
+}
+
+impl<'a> Batch<'a> {
+    pub fn new(base: &'a Computer) -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self { base, threads }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    fn run_variant(&self, index: usize, variant: Variant) -> Outcome {
+        let mut computer = self.base.clone();
+        computer.input = variant.input.or(computer.input);
+        let result = match variant
+            .patches
+            .iter()
+            .find(|(address, _)| *address >= computer.len())
+        {
+            Some(&(address, _)) => Err(ComputerError::OutOfBounds {
+                pointer: computer.pointer,
+                address,
+                size: computer.len(),
+            }),
+            None => {
+                for &(address, value) in &variant.patches {
+                    computer.memory[address] = value;
+                }
+                computer.run_program()
+            }
+        };
+        Outcome {
+            index,
+            variant,
+            result,
+            computer,
+        }
+    }
+
+    /// Shared worker loop. Stops handing out variants past `stop_after` once a
+    /// variant matches, so every variant before the first match still runs.
+    fn execute<I, F>(&self, variants: I, is_match: Option<F>) -> Vec<Outcome>
+    where
+        I: IntoIterator<Item = Variant>,
+        I::IntoIter: Send,
+        F: Fn(&Outcome) -> bool + Sync,
+    {
+        let queue = Mutex::new(variants.into_iter().enumerate());
+        let stop_after = AtomicUsize::new(usize::MAX);
+        let outcomes = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+                    let Some((index, variant)) = next else {
+                        break;
+                    };
+                    if index > stop_after.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    let outcome = self.run_variant(index, variant);
+                    if let Some(is_match) = &is_match {
+                        if is_match(&outcome) {
+                            stop_after.fetch_min(index, Ordering::SeqCst);
+                        }
+                    }
+                    outcomes.lock().unwrap().push(outcome);
+                });
+            }
+        });
+
+        let mut outcomes = outcomes.into_inner().unwrap();
+        outcomes.sort_by_key(|outcome| outcome.index);
+        outcomes
+    }
+
+    /// Runs every variant, returning outcomes in input order.
+    pub fn run<I>(&self, variants: I) -> Vec<Outcome>
+    where
+        I: IntoIterator<Item = Variant>,
+        I::IntoIter: Send,
+    {
+        self.execute(variants, None::<fn(&Outcome) -> bool>)
+    }
+
+    /// First variant, in input order, whose outcome matches. Variants after a
+    /// match are skipped.
+    pub fn find<I, F>(&self, variants: I, is_match: F) -> Option<Outcome>
+    where
+        I: IntoIterator<Item = Variant>,
+        I::IntoIter: Send,
+        F: Fn(&Outcome) -> bool + Sync,
+    {
+        self.execute(variants, Some(&is_match))
+            .into_iter()
+            .find(|outcome| is_match(outcome))
+    }
+}
+
+impl Computer {
+    pub fn batch(&self) -> Batch<'_> {
+        Batch::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let computer = Computer::from("1,0,0,0,99");
+        let outcomes = computer
+            .batch()
+            .threads(4)
+            .run((0..6).map(|noun| Variant::patch(&[(1, noun)])));
+        let results = outcomes
+            .iter()
+            .map(|outcome| outcome.result.clone().ok())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            results,
+            vec![Some(2), Some(2), Some(1), Some(1), Some(100), None]
+        );
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|outcome| outcome.index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4, 5]
+        );
+
+        let outcome = &computer.batch().run([Variant::patch(&[(5, 0)])])[0];
+        assert_eq!(
+            outcome.result,
+            Err(ComputerError::OutOfBounds {
+                pointer: 0,
+                address: 5,
+                size: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        // Equal to 8 (position mode)
+        let computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        let found = computer
+            .batch()
+            .threads(3)
+            .find((0..100).map(Variant::input), |outcome| {
+                outcome.computer.output == vec![1]
+            })
+            .unwrap();
+        assert_eq!(found.index, 8);
+        assert_eq!(found.variant, Variant::input(8));
+
+        let found = computer
+            .batch()
+            .find((0..5).map(Variant::input), |outcome| {
+                outcome.result.is_err()
+            });
+        assert!(found.is_none());
+    }
+}
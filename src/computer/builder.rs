@@ -0,0 +1,294 @@
+use std::ops::ControlFlow;
+
+use super::diverge::{self, TraceStep};
+use super::gas::CostTable;
+use super::sandbox::Sandbox;
+use super::{Arithmetic, Computer, ComputerError, Isa, Memory, Validation};
+
+/// Supplies a value when `Input` runs with no queued input left. `None`
+/// leaves the computer to fall back to `Computer::input`.
+pub type InputDevice = Box<dyn FnMut() -> Option<i32>>;
+/// Receives every output as it is produced.
+pub type OutputDevice = Box<dyn FnMut(i32)>;
+
+/// Why a run stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Halt {
+    /// The program ran `Exit`, or a custom instruction halted.
+    Exit,
+    /// The step limit was reached before the program stopped.
+    StepLimit,
+    Error(ComputerError),
+}
+
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub halt: Halt,
+    pub output: Vec<i32>,
+    pub memory: Memory,
+    /// Number of instructions run, including the one that stopped the run.
+    pub steps: usize,
+    /// Every step, if tracing was enabled.
+    pub trace: Vec<TraceStep>,
+}
+
+impl RunResult {
+    pub fn is_exit(&self) -> bool {
+        self.halt == Halt::Exit
+    }
+}
+
+/// Configures a `Computer` and runs it.
+#[derive(Default)]
+pub struct ComputerBuilder {
+    memory: Memory,
+    patches: Vec<(usize, i32)>,
+    inputs: Vec<i32>,
+    isa: Isa,
+    arithmetic: Arithmetic,
+    validation: Validation,
+    max_steps: Option<usize>,
+    detect_loops: bool,
+    gas: Option<(u64, CostTable)>,
+    sandbox: Sandbox,
+    trace: bool,
+    input_device: Option<InputDevice>,
+    output_device: Option<OutputDevice>,
+}
+
+impl ComputerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Comma-separated program source, as accepted by `Computer::from`.
+    pub fn program(mut self, program: &str) -> Self {
+        self.memory = Computer::from(program).memory;
+        self
+    }
+
+    pub fn memory(mut self, memory: Memory) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    /// Writes `value` to `address` before running.
+    pub fn patch(mut self, address: usize, value: i32) -> Self {
+        self.patches.push((address, value));
+        self
+    }
+
+    /// Appends values consumed in order by `Input`.
+    pub fn input<I: IntoIterator<Item = i32>>(mut self, inputs: I) -> Self {
+        self.inputs.extend(inputs);
+        self
+    }
+
+    pub fn isa(mut self, isa: Isa) -> Self {
+        self.isa = isa;
+        self
+    }
+
+    pub fn arithmetic(mut self, arithmetic: Arithmetic) -> Self {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub fn validation(mut self, validation: Validation) -> Self {
+        self.validation = validation;
+        self
+    }
+
+    /// Stops with `Halt::StepLimit` after `max_steps` instructions. Runs are
+    /// unlimited by default.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Meters the run, see `Computer::meter`.
+    pub fn gas(mut self, budget: u64, costs: CostTable) -> Self {
+        self.gas = Some((budget, costs));
+        self
+    }
+
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    pub fn detect_loops(mut self) -> Self {
+        self.detect_loops = true;
+        self
+    }
+
+    /// Records every step in `RunResult::trace`.
+    pub fn trace(mut self) -> Self {
+        self.trace = true;
+        self
+    }
+
+    pub fn input_device<F: FnMut() -> Option<i32> + 'static>(mut self, device: F) -> Self {
+        self.input_device = Some(Box::new(device));
+        self
+    }
+
+    pub fn output_device<F: FnMut(i32) + 'static>(mut self, device: F) -> Self {
+        self.output_device = Some(Box::new(device));
+        self
+    }
+
+    /// The configured computer, without devices or step limits. Fails if a
+    /// patch is outside the program.
+    pub fn build(&self) -> Result<Computer, ComputerError> {
+        let mut computer = Computer::new_with(self.memory.clone());
+        for &(address, value) in &self.patches {
+            if address >= computer.len() {
+                return Err(computer.out_of_bounds(address));
+            }
+            computer.memory[address] = value;
+        }
+        computer.inputs.extend(&self.inputs);
+        computer.isa = self.isa;
+        computer.arithmetic = self.arithmetic;
+        computer.validation = self.validation;
+        computer.sandbox = self.sandbox;
+        if self.detect_loops {
+            computer.detect_loops();
+        }
+        if let Some((budget, costs)) = self.gas {
+            computer.meter(budget, costs);
+        }
+        Ok(computer)
+    }
+
+    /// Builds and runs the computer. Only configuration errors are returned
+    /// as `Err`, errors while running end up in `RunResult::halt`.
+    pub fn run(mut self) -> Result<RunResult, ComputerError> {
+        let mut computer = self.build()?;
+        let mut trace = vec![];
+        let mut steps = 0;
+
+        let halt = loop {
+            if matches!(self.max_steps, Some(max) if steps >= max) {
+                break Halt::StepLimit;
+            }
+            if let Some(device) = &mut self.input_device {
+                if computer.at_input() && computer.inputs.is_empty() {
+                    computer.inputs.extend(device());
+                }
+            }
+
+            let from = computer.pointer;
+            let outputs = computer.output.len();
+            let stopped = if self.trace {
+                let step = diverge::step(&mut computer, steps);
+                let stopped = step.halt.clone();
+                trace.push(step);
+                stopped
+            } else {
+                match computer.run_command() {
+                    ControlFlow::Break(res) => Some(res),
+                    ControlFlow::Continue(_) => None,
+                }
+            };
+            steps += 1;
+
+            if let Some(device) = &mut self.output_device {
+                computer.output[outputs..]
+                    .iter()
+                    .for_each(|&value| device(value));
+            }
+            match stopped {
+                Some(Ok(_)) => break Halt::Exit,
+                Some(Err(err)) => break Halt::Error(err),
+                None => {}
+            }
+            if let Err(err) = computer.check_loop(from) {
+                break Halt::Error(err);
+            }
+        };
+
+        Ok(RunResult {
+            halt,
+            output: computer.output,
+            memory: computer.memory,
+            steps,
+            trace,
+        })
+    }
+}
+
+impl Computer {
+    pub fn builder() -> ComputerBuilder {
+        ComputerBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        let result = Computer::builder()
+            .program("1,0,0,0,99")
+            .patch(1, 4)
+            .patch(2, 4)
+            .isa(Isa::Day2)
+            .run()
+            .unwrap();
+        assert!(result.is_exit());
+        assert_eq!(result.memory, vec![198, 4, 4, 0, 99]);
+        assert_eq!(result.steps, 2);
+
+        let result = Computer::builder()
+            .program("3,0,3,1,4,0,4,1,99")
+            .input([7, 8])
+            .trace()
+            .run()
+            .unwrap();
+        assert_eq!(result.output, vec![7, 8]);
+        assert_eq!(result.trace.len(), 5);
+        assert_eq!(result.trace[1].write, Some((1, 8)));
+
+        let result = Computer::builder()
+            .program("1105,1,0")
+            .max_steps(10)
+            .run()
+            .unwrap();
+        assert_eq!(result.halt, Halt::StepLimit);
+        assert_eq!(result.steps, 10);
+
+        assert!(matches!(
+            Computer::builder().program("99").patch(1, 0).run(),
+            Err(ComputerError::OutOfBounds { address: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_devices() {
+        let outputs = Rc::new(RefCell::new(vec![]));
+        let sink = outputs.clone();
+        let mut next = 0;
+        // Doubles three inputs.
+        let result = Computer::builder()
+            .program("3,13,102,2,13,13,4,13,1005,13,0,99,0,0")
+            .input_device(move || {
+                next += 1;
+                (next <= 3).then_some(next)
+            })
+            .output_device(move |value| sink.borrow_mut().push(value))
+            .run()
+            .unwrap();
+        assert_eq!(
+            result.halt,
+            Halt::Error(ComputerError::MissingInput { pointer: 0 })
+        );
+        assert_eq!(*outputs.borrow(), vec![2, 4, 6]);
+        assert_eq!(result.output, vec![2, 4, 6]);
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::disasm::{relative, Instruction};
+use super::memory::Cells;
+use super::{Computer, OpCode, ParameterMode};
+
+/// Where a jump goes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    /// Immediate-mode target, known without running the program.
+    Static(usize),
+    /// Position-mode target, read from the given cell at runtime.
+    Indirect(usize),
+    /// Relative-mode target, read from `relative_base + offset` at runtime.
+    Relative(i32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edge {
+    /// Execution continues with the next instruction in memory.
+    Fallthrough(usize),
+    /// A `JumpTrue`/`JumpFalse` that is taken.
+    Jump(Target),
+}
+
+impl Edge {
+    pub fn target(&self) -> Target {
+        match self {
+            Edge::Fallthrough(address) => Target::Static(*address),
+            Edge::Jump(target) => target.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: Vec<Instruction>,
+    pub successors: Vec<Edge>,
+}
+
+impl BasicBlock {
+    /// First address after the block.
+    pub fn end(&self) -> usize {
+        self.instructions
+            .last()
+            .map_or(self.start, |instruction| instruction.next())
+    }
+
+    pub fn halts(&self) -> bool {
+        matches!(
+            self.instructions.last(),
+            Some(Instruction {
+                op: OpCode::Exit,
+                ..
+            })
+        )
+    }
+
+    /// A block is invalid when its first instruction can't be decoded.
+    pub fn is_invalid(&self) -> bool {
+        self.instructions.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlFlowGraph {
+    pub entry: usize,
+    pub blocks: BTreeMap<usize, BasicBlock>,
+}
+
+/// The edges leaving `instruction`, or `None` if it doesn't end a block.
+fn branch_edges(instruction: &Instruction) -> Option<Vec<Edge>> {
+    let (jump_when_non_zero, condition, target) = match instruction.op {
+        OpCode::JumpTrue => (true, instruction.params[0], instruction.params[1]),
+        OpCode::JumpFalse => (false, instruction.params[0], instruction.params[1]),
+        OpCode::Exit => return Some(vec![]),
+        _ => return None,
+    };
+
+    let taken = match instruction.modes[1] {
+        ParameterMode::Immediate => Target::Static(target as usize),
+        ParameterMode::Position => Target::Indirect(target as usize),
+        ParameterMode::Relative => Target::Relative(target),
+    };
+    let taken = Edge::Jump(taken);
+    let fallthrough = Edge::Fallthrough(instruction.next());
+
+    match instruction.modes[0] {
+        ParameterMode::Immediate if (condition != 0) == jump_when_non_zero => Some(vec![taken]),
+        ParameterMode::Immediate => Some(vec![fallthrough]),
+        ParameterMode::Position | ParameterMode::Relative => Some(vec![taken, fallthrough]),
+    }
+}
+
+impl ControlFlowGraph {
+    /// Discovers basic blocks reachable from `entry` by following fallthrough
+    /// and statically known jump targets. Indirect targets are recorded on the
+    /// edge but not followed.
+    pub fn new<M: Cells + ?Sized>(memory: &M, entry: usize) -> Self {
+        let mut leaders = BTreeSet::from([entry]);
+        let mut visited = BTreeSet::new();
+        let mut queue = vec![entry];
+
+        while let Some(mut address) = queue.pop() {
+            while visited.insert(address) {
+                let Some(instruction) = Instruction::decode(memory, address) else {
+                    break;
+                };
+                if let Some(edges) = branch_edges(&instruction) {
+                    for edge in edges {
+                        if let Target::Static(target) = edge.target() {
+                            if leaders.insert(target) {
+                                queue.push(target);
+                            }
+                        }
+                    }
+                    break;
+                }
+                address = instruction.next();
+            }
+        }
+
+        let blocks = leaders
+            .iter()
+            .map(|&start| (start, Self::build_block(memory, start, &leaders)))
+            .collect();
+
+        Self { entry, blocks }
+    }
+
+    fn build_block<M: Cells + ?Sized>(
+        memory: &M,
+        start: usize,
+        leaders: &BTreeSet<usize>,
+    ) -> BasicBlock {
+        let mut instructions = vec![];
+        let mut successors = vec![];
+        let mut address = start;
+
+        while let Some(instruction) = Instruction::decode(memory, address) {
+            address = instruction.next();
+            let edges = branch_edges(&instruction);
+            instructions.push(instruction);
+            if let Some(edges) = edges {
+                successors = edges;
+                break;
+            }
+            if leaders.contains(&address) {
+                successors = vec![Edge::Fallthrough(address)];
+                break;
+            }
+        }
+
+        BasicBlock {
+            start,
+            instructions,
+            successors,
+        }
+    }
+
+    pub fn block_at(&self, address: usize) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=address)
+            .next_back()
+            .map(|(_, block)| block)
+            .filter(|block| address < block.end() || block.start == address)
+    }
+
+    /// Addresses of jump instructions whose target is only known at runtime.
+    pub fn indirect_jumps(&self) -> Vec<usize> {
+        self.blocks
+            .values()
+            .filter(|block| {
+                block
+                    .successors
+                    .iter()
+                    .any(|edge| !matches!(edge.target(), Target::Static(_)))
+            })
+            .filter_map(|block| block.instructions.last())
+            .map(|instruction| instruction.address)
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT format with one node per block.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        writeln!(dot, "digraph intcode {{").unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+        let mut indirect = BTreeMap::new();
+        for block in self.blocks.values() {
+            let label = if block.is_invalid() {
+                format!("{:04}: <invalid>\\l", block.start)
+            } else {
+                block
+                    .instructions
+                    .iter()
+                    .map(|instruction| format!("{instruction}\\l"))
+                    .collect()
+            };
+            writeln!(dot, "    b{} [label=\"{label}\"];", block.start).unwrap();
+
+            for edge in &block.successors {
+                let (kind, style) = match edge {
+                    Edge::Fallthrough(_) => ("fallthrough", ""),
+                    Edge::Jump(Target::Static(_)) => ("jump", ""),
+                    Edge::Jump(_) => ("jump", ", style=dashed"),
+                };
+                let to = match edge.target() {
+                    Target::Static(address) => format!("b{address}"),
+                    Target::Indirect(cell) => {
+                        indirect.insert(format!("indirect_{cell}"), format!("[{cell}]"));
+                        format!("indirect_{cell}")
+                    }
+                    Target::Relative(offset) => {
+                        let node = format!("\"relative_{offset}\"");
+                        indirect.insert(node.clone(), format!("[{}]", relative(offset)));
+                        node
+                    }
+                };
+                writeln!(
+                    dot,
+                    "    b{} -> {to} [label=\"{kind}\"{style}];",
+                    block.start
+                )
+                .unwrap();
+            }
+        }
+
+        for (node, label) in indirect {
+            writeln!(
+                dot,
+                "    {node} [label=\"{label}\", shape=diamond, style=dashed];"
+            )
+            .unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+}
+
+impl Computer {
+    pub fn control_flow_graph(&self) -> ControlFlowGraph {
+        ControlFlowGraph::new(&self.memory, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LARGER_PROGRAM: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+    #[test]
+    fn test_blocks() {
+        let computer = Computer::from(LARGER_PROGRAM);
+        let cfg = computer.control_flow_graph();
+        assert_eq!(
+            cfg.blocks.keys().copied().collect::<Vec<_>>(),
+            vec![0, 9, 16, 22, 31, 36, 46]
+        );
+
+        let entry = &cfg.blocks[&0];
+        assert_eq!(entry.end(), 9);
+        assert_eq!(
+            entry.successors,
+            vec![Edge::Jump(Target::Static(22)), Edge::Fallthrough(9)]
+        );
+
+        // `1106,0,36` always jumps
+        assert_eq!(
+            cfg.blocks[&16].successors,
+            vec![Edge::Jump(Target::Static(36))]
+        );
+        assert!(cfg.blocks[&46].halts());
+        assert_eq!(cfg.block_at(4).map(|block| block.start), Some(0));
+        assert!(cfg.indirect_jumps().is_empty());
+    }
+
+    #[test]
+    fn test_indirect() {
+        let computer = Computer::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        let cfg = computer.control_flow_graph();
+        assert_eq!(
+            cfg.blocks[&0].successors,
+            vec![Edge::Jump(Target::Indirect(15)), Edge::Fallthrough(5)]
+        );
+        assert_eq!(cfg.indirect_jumps(), vec![2]);
+        // 9 is only reachable through the indirect jump
+        assert!(!cfg.blocks.contains_key(&9));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let computer = Computer::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        let dot = computer.control_flow_graph().to_dot();
+        assert!(dot.starts_with("digraph intcode {\n"));
+        assert!(dot.contains("b0 [label=\"0000: IN [12]\\l0002: JF [12], [15]\\l\"];"));
+        assert!(dot.contains("b0 -> indirect_15 [label=\"jump\", style=dashed];"));
+        assert!(dot.contains("b0 -> b5 [label=\"fallthrough\"];"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::ops::ControlFlow;
+
+use super::cfg::ControlFlowGraph;
+use super::disasm::Instruction;
+use super::memory::Cells;
+use super::Computer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellKind {
+    /// Part of an instruction that is reachable or was executed.
+    Code,
+    /// Read or written as an operand, never executed.
+    Data,
+    /// Executed after being written by the program itself.
+    SelfModifiedCode,
+    Unknown,
+}
+
+/// Labels every address of a memory image as code or data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub cells: Vec<CellKind>,
+}
+
+#[derive(Debug, Default)]
+struct Usage {
+    executed: BTreeSet<usize>,
+    accessed: BTreeSet<usize>,
+    written: BTreeSet<usize>,
+}
+
+impl Usage {
+    fn record(&mut self, instruction: &Instruction) {
+        self.executed
+            .extend(instruction.address..instruction.next());
+        self.accessed.extend(instruction.read_addresses());
+        self.accessed.extend(instruction.write_address());
+    }
+}
+
+impl Classification {
+    /// Classifies cells using static reachability from address 0 only.
+    pub fn new<M: Cells + ?Sized>(memory: &M) -> Self {
+        Self::from_usage(memory.len(), &Self::static_usage(memory), &Usage::default())
+    }
+
+    /// Combines static reachability with a run of a clone of `computer`
+    /// limited to `max_steps` instructions. Cells that are written during the
+    /// run and later executed are labelled `SelfModifiedCode`.
+    pub fn with_run(computer: &Computer, max_steps: usize) -> Self {
+        let static_usage = Self::static_usage(&computer.memory);
+        let mut computer = computer.clone();
+        let mut dynamic = Usage::default();
+        let mut self_modified = BTreeSet::new();
+
+        for _ in 0..max_steps {
+            let Some(instruction) = Instruction::decode(&computer.memory, computer.pointer) else {
+                break;
+            };
+            self_modified.extend(
+                (instruction.address..instruction.next())
+                    .filter(|address| dynamic.written.contains(address)),
+            );
+            dynamic.record(&instruction);
+            dynamic.written.extend(instruction.write_address());
+            if let ControlFlow::Break(_) = computer.run_command() {
+                break;
+            }
+        }
+
+        let mut res = Self::from_usage(computer.len(), &static_usage, &dynamic);
+        for address in self_modified {
+            res.cells[address] = CellKind::SelfModifiedCode;
+        }
+        res
+    }
+
+    fn static_usage<M: Cells + ?Sized>(memory: &M) -> Usage {
+        let mut usage = Usage::default();
+        let cfg = ControlFlowGraph::new(memory, 0);
+        for instruction in cfg.blocks.values().flat_map(|block| &block.instructions) {
+            usage.record(instruction);
+        }
+        usage
+    }
+
+    fn from_usage(len: usize, static_usage: &Usage, dynamic: &Usage) -> Self {
+        let cells = (0..len)
+            .map(|address| {
+                let is = |set: fn(&Usage) -> &BTreeSet<usize>| {
+                    set(static_usage).contains(&address) || set(dynamic).contains(&address)
+                };
+                if is(|usage| &usage.executed) {
+                    CellKind::Code
+                } else if is(|usage| &usage.accessed) {
+                    CellKind::Data
+                } else {
+                    CellKind::Unknown
+                }
+            })
+            .collect();
+        Self { cells }
+    }
+
+    pub fn kind(&self, address: usize) -> CellKind {
+        self.cells
+            .get(address)
+            .copied()
+            .unwrap_or(CellKind::Unknown)
+    }
+
+    /// Disassembles code cells and prints everything else as raw values.
+    pub fn listing<M: Cells + ?Sized>(&self, memory: &M) -> String {
+        let value = |address| memory.cell(address).unwrap();
+        let mut res = String::new();
+        let mut address = 0;
+        while address < memory.len() {
+            let kind = self.kind(address);
+            let instruction = match kind {
+                CellKind::Code | CellKind::SelfModifiedCode => Instruction::decode(memory, address),
+                _ => None,
+            };
+            match (instruction, kind) {
+                (Some(instruction), CellKind::SelfModifiedCode) => {
+                    writeln!(res, "{instruction} ; self-modified").unwrap();
+                    address = instruction.next();
+                }
+                (Some(instruction), _) => {
+                    writeln!(res, "{instruction}").unwrap();
+                    address = instruction.next();
+                }
+                (None, CellKind::Data) => {
+                    writeln!(res, "{address:04}: DATA {}", value(address)).unwrap();
+                    address += 1;
+                }
+                (None, _) => {
+                    writeln!(res, "{address:04}: ?? {}", value(address)).unwrap();
+                    address += 1;
+                }
+            }
+        }
+        res
+    }
+}
+
+impl Computer {
+    pub fn classify(&self) -> Classification {
+        Classification::new(&self.memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static() {
+        // Equal to 8 (position mode)
+        let computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        let classification = computer.classify();
+        assert_eq!(
+            classification.cells,
+            [vec![CellKind::Code; 9], vec![CellKind::Data; 2]].concat()
+        );
+        assert_eq!(
+            classification.listing(&computer.memory),
+            "0000: IN [9]\n0002: EQ [9], [10], [9]\n0006: OUT [9]\n0008: HALT\n0009: DATA -1\n0010: DATA 8\n"
+        );
+    }
+
+    #[test]
+    fn test_with_run() {
+        let mut computer = Computer::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9");
+        let classification = computer.classify();
+        assert_eq!(classification.kind(15), CellKind::Data);
+
+        computer.input = Some(0);
+        let classification = Classification::with_run(&computer, 100);
+        assert_eq!(classification.kind(9), CellKind::Code);
+        assert_eq!(classification.kind(13), CellKind::Data);
+    }
+
+    #[test]
+    fn test_self_modified() {
+        // writes 99 over the `1101` at 4 before executing it
+        let computer = Computer::from("1101,0,99,4,1101,1,1,0");
+        let classification = Classification::with_run(&computer, 100);
+        assert_eq!(classification.kind(0), CellKind::Code);
+        assert_eq!(classification.kind(4), CellKind::SelfModifiedCode);
+        assert_eq!(classification.kind(5), CellKind::Code);
+    }
+}
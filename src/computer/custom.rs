@@ -0,0 +1,185 @@
+use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use super::{Computer, ComputerError, Digits, OpCode, ParameterMode};
+
+/// Most parameters a custom instruction can have: `u32::MAX` has ten digits,
+/// two of which are the opcode and the top one can't be every mode.
+const MAX_ARITY: usize = 7;
+
+/// What the machine does after a custom instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Continue with the instruction after this one.
+    Next,
+    Jump(usize),
+    Halt,
+}
+
+/// Called with the computer and the resolved address of each parameter.
+/// Immediate-mode parameters resolve to the parameter's own cell, as for the
+/// built-in instructions.
+pub type Handler =
+    Arc<dyn Fn(&mut Computer, &[usize]) -> Result<Step, ComputerError> + Send + Sync>;
+
+/// An instruction added to a `Computer` at runtime. Static tools such as the
+/// disassembler only know the built-in set and treat these as unknown.
+#[derive(Clone)]
+pub struct CustomOp {
+    pub name: String,
+    pub arity: usize,
+    pub handler: Handler,
+}
+
+impl fmt::Debug for CustomOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CustomOp")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Computer {
+    /// Registers `handler` for opcode `code`. Built-in opcodes can't be
+    /// replaced, codes must fit in the two opcode digits and the mode digits
+    /// of all `arity` parameters must fit in a `u32` instruction.
+    pub fn register_op<F>(
+        &mut self,
+        code: u32,
+        name: &str,
+        arity: usize,
+        handler: F,
+    ) -> Result<(), ComputerError>
+    where
+        F: Fn(&mut Computer, &[usize]) -> Result<Step, ComputerError> + Send + Sync + 'static,
+    {
+        if OpCode::decode(code).is_some() || code > 99 || arity > MAX_ARITY {
+            return Err(ComputerError::InvalidCustomOp { code, arity });
+        }
+        self.custom_ops.insert(
+            code,
+            CustomOp {
+                name: name.to_string(),
+                arity,
+                handler: Arc::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    pub(super) fn run_custom(
+        &mut self,
+        op: &CustomOp,
+    ) -> ControlFlow<Result<String, ComputerError>, i32> {
+        match self.custom(op) {
+            Ok(Step::Next) => {
+                self.pointer += 1 + op.arity;
+                ControlFlow::Continue(0)
+            }
+            Ok(Step::Jump(address)) => {
+                self.pointer = address;
+                ControlFlow::Continue(0)
+            }
+            Ok(Step::Halt) => ControlFlow::Break(Ok(format!("exit {}", op.name))),
+            Err(err) => ControlFlow::Break(Err(err)),
+        }
+    }
+
+    fn custom(&mut self, op: &CustomOp) -> Result<Step, ComputerError> {
+        let value = self.memory[self.pointer];
+        let command = u32::try_from(value).map_err(|_| ComputerError::UnknownOpCode {
+            pointer: self.pointer,
+            value,
+        })?;
+        let mut addresses = vec![];
+        for param in 0..op.arity {
+            let mode = ParameterMode::decode(command.digit(param as u32 + 2))
+                .filter(|mode| self.isa.supports_mode(mode))
+                .ok_or(ComputerError::InvalidMode {
+                    pointer: self.pointer,
+                    value,
+                })?;
+            addresses.push(self.get_pos(1 + param, &mode)?);
+        }
+        (op.handler)(self, &addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extended(program: &str) -> Computer {
+        let mut computer = Computer::from(program);
+        computer
+            .register_op(10, "double", 2, |computer, params| {
+                computer.memory[params[1]] = computer.memory[params[0]] * 2;
+                Ok(Step::Next)
+            })
+            .unwrap();
+        computer
+            .register_op(11, "jump", 1, |computer, params| {
+                Ok(Step::Jump(computer.memory[params[0]] as usize))
+            })
+            .unwrap();
+        computer
+    }
+
+    #[test]
+    fn test_custom_ops() {
+        let mut computer = extended("10,7,0,111,6,0,99,21");
+        assert_eq!(computer.run_program(), Ok(42));
+
+        let mut computer = extended("10,9,0,99");
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::OutOfBounds {
+                pointer: 0,
+                address: 9,
+                size: 4
+            })
+        );
+
+        // -5 wraps to opcode 91 as a `u32`.
+        let mut computer = Computer::from("-5,99");
+        computer
+            .register_op(91, "wrapped", 0, |_, _| Ok(Step::Halt))
+            .unwrap();
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::UnknownOpCode {
+                pointer: 0,
+                value: -5
+            })
+        );
+    }
+
+    #[test]
+    fn test_register() {
+        let mut computer = Computer::new();
+        assert_eq!(
+            computer.register_op(1, "add", 3, |_, _| Ok(Step::Next)),
+            Err(ComputerError::InvalidCustomOp { code: 1, arity: 3 })
+        );
+        assert_eq!(
+            computer
+                .register_op(100, "big", 0, |_, _| Ok(Step::Next))
+                .unwrap_err()
+                .to_string(),
+            "Opcode 100 with 0 parameters is built in or doesn't fit in an instruction"
+        );
+        assert_eq!(
+            computer.register_op(13, "wide", 9, |_, _| Ok(Step::Next)),
+            Err(ComputerError::InvalidCustomOp { code: 13, arity: 9 })
+        );
+        assert!(computer
+            .register_op(12, "halt", 0, |_, _| Ok(Step::Halt))
+            .is_ok());
+        assert_eq!(
+            format!("{:?}", computer.custom_ops[&12]),
+            "CustomOp { name: \"halt\", arity: 0, .. }"
+        );
+    }
+}
@@ -0,0 +1,892 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::cfg::{BasicBlock, ControlFlowGraph, Edge, Target};
+use super::disasm::Instruction;
+use super::memory::Cells;
+use super::{Computer, OpCode, ParameterMode};
+
+/// Stand-in for the program end when computing post-dominators.
+const EXIT: usize = usize::MAX;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(i32),
+    /// The memory cell at the given address.
+    Var(usize),
+    /// The memory cell at `relative_base + offset`.
+    Relative(i32),
+    Input,
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Flag(Box<Cond>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CmpOp {
+    Lt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cond {
+    pub lhs: Expr,
+    pub op: CmpOp,
+    pub rhs: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// Assigns to a `Var` or `Relative` cell.
+    Assign(Expr, Expr),
+    AdjustBase(Expr),
+    Output(Expr),
+    If(Cond, Vec<Stmt>, Vec<Stmt>),
+    Loop(Vec<Stmt>),
+    While(Cond, Vec<Stmt>),
+    Break,
+    Continue,
+    Return,
+    /// Execution reaches an address that doesn't decode.
+    Invalid(usize),
+    /// Unstructured fallback: a `pc` driven loop over the basic blocks.
+    Dispatch(Vec<(usize, Vec<Stmt>)>),
+    /// Sets `pc` in a dispatch loop, optionally depending on a condition.
+    Goto(Option<Cond>, Target, Option<usize>),
+}
+
+/// Structured pseudocode for a program, printed as Rust-like source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decompiled {
+    /// Memory cells used as variables, with their initial values.
+    pub variables: BTreeMap<usize, i32>,
+    /// Whether the program uses relative mode and needs `rb` declared.
+    pub relative_base: bool,
+    /// The initial image, declared as `mem` when `relative_base` is set.
+    /// Relative-mode cells are indexed into it, separately from the `m`
+    /// variables.
+    pub memory: Vec<i32>,
+    pub body: Vec<Stmt>,
+    /// False when control flow couldn't be recovered and `body` is a dispatch
+    /// loop.
+    pub structured: bool,
+}
+
+impl CmpOp {
+    fn negate(self) -> Self {
+        match self {
+            CmpOp::Lt => CmpOp::Ge,
+            CmpOp::Ge => CmpOp::Lt,
+            CmpOp::Eq => CmpOp::Ne,
+            CmpOp::Ne => CmpOp::Eq,
+        }
+    }
+}
+
+impl Cond {
+    pub fn negate(&self) -> Self {
+        Self {
+            lhs: self.lhs.clone(),
+            op: self.op.negate(),
+            rhs: self.rhs.clone(),
+        }
+    }
+
+    /// `value != 0`
+    fn non_zero(value: Expr) -> Self {
+        Self {
+            lhs: value,
+            op: CmpOp::Ne,
+            rhs: Expr::Literal(0),
+        }
+    }
+}
+
+impl Expr {
+    fn operand(instruction: &Instruction, param: usize) -> Self {
+        match instruction.modes[param] {
+            ParameterMode::Position => Expr::Var(instruction.params[param] as usize),
+            ParameterMode::Immediate => Expr::Literal(instruction.params[param]),
+            ParameterMode::Relative => Expr::Relative(instruction.params[param]),
+        }
+    }
+
+    /// The cell written through a destination parameter.
+    fn destination(instruction: &Instruction, param: usize) -> Self {
+        match instruction.modes[param] {
+            ParameterMode::Immediate => Expr::Var(instruction.address + 1 + param),
+            _ => Expr::operand(instruction, param),
+        }
+    }
+
+    fn vars(&self, vars: &mut BTreeSet<usize>) {
+        match self {
+            Expr::Var(address) => {
+                vars.insert(*address);
+            }
+            Expr::Add(a, b) | Expr::Mul(a, b) => {
+                a.vars(vars);
+                b.vars(vars);
+            }
+            Expr::Flag(cond) => cond.vars(vars),
+            Expr::Literal(_) | Expr::Relative(_) | Expr::Input => {}
+        }
+    }
+}
+
+impl Cond {
+    fn vars(&self, vars: &mut BTreeSet<usize>) {
+        self.lhs.vars(vars);
+        self.rhs.vars(vars);
+    }
+}
+
+impl Stmt {
+    fn vars(&self, vars: &mut BTreeSet<usize>) {
+        match self {
+            Stmt::Assign(to, expr) => {
+                to.vars(vars);
+                expr.vars(vars);
+            }
+            Stmt::Output(expr) | Stmt::AdjustBase(expr) => expr.vars(vars),
+            Stmt::If(cond, then, otherwise) => {
+                cond.vars(vars);
+                then.iter()
+                    .chain(otherwise)
+                    .for_each(|stmt| stmt.vars(vars));
+            }
+            Stmt::While(cond, body) => {
+                cond.vars(vars);
+                body.iter().for_each(|stmt| stmt.vars(vars));
+            }
+            Stmt::Loop(body) => body.iter().for_each(|stmt| stmt.vars(vars)),
+            Stmt::Dispatch(blocks) => blocks
+                .iter()
+                .flat_map(|(_, body)| body)
+                .for_each(|stmt| stmt.vars(vars)),
+            Stmt::Goto(cond, target, _) => {
+                if let Some(cond) = cond {
+                    cond.vars(vars);
+                }
+                if let Target::Indirect(address) = target {
+                    vars.insert(*address);
+                }
+            }
+            Stmt::Break | Stmt::Continue | Stmt::Return | Stmt::Invalid(_) => {}
+        }
+    }
+}
+
+/// Lifts a block's instructions, leaving out the final jump or halt. Returns
+/// the statements and, for conditional blocks, the jump condition.
+fn lift_block(block: &BasicBlock, dead_flags: &BTreeSet<usize>) -> (Vec<Stmt>, Option<Cond>) {
+    let mut stmts = vec![];
+    let mut flag = None;
+    for instruction in &block.instructions {
+        let a = || Expr::operand(instruction, 0);
+        let b = || Expr::operand(instruction, 1);
+        let to = || Expr::destination(instruction, instruction.op.arity() - 1);
+        let prev = flag.take();
+        match instruction.op {
+            OpCode::Add => stmts.push(Stmt::Assign(to(), Expr::Add(a().into(), b().into()))),
+            OpCode::Mul => stmts.push(Stmt::Assign(to(), Expr::Mul(a().into(), b().into()))),
+            OpCode::Input => stmts.push(Stmt::Assign(to(), Expr::Input)),
+            OpCode::Output => stmts.push(Stmt::Output(a())),
+            OpCode::AdjustBase => stmts.push(Stmt::AdjustBase(a())),
+            OpCode::LessThan | OpCode::Equal => {
+                let op = if instruction.op == OpCode::LessThan {
+                    CmpOp::Lt
+                } else {
+                    CmpOp::Eq
+                };
+                let cond = Cond {
+                    lhs: a(),
+                    op,
+                    rhs: b(),
+                };
+                let mut read = BTreeSet::new();
+                cond.vars(&mut read);
+                // Only fold a flag nothing else reads, and only if writing it
+                // doesn't change the operands the jump would then compare.
+                let fold = match to() {
+                    Expr::Var(address) => dead_flags.contains(&address) && !read.contains(&address),
+                    _ => false,
+                };
+                if fold {
+                    flag = Some((to(), cond));
+                } else {
+                    stmts.push(Stmt::Assign(to(), Expr::Flag(cond.into())));
+                }
+            }
+            OpCode::JumpTrue | OpCode::JumpFalse => {
+                let value = Expr::operand(instruction, 0);
+                let cond = match (&value, prev) {
+                    (value, Some((to, cond))) if *value == to => cond,
+                    _ => Cond::non_zero(value),
+                };
+                let cond = if instruction.op == OpCode::JumpTrue {
+                    cond
+                } else {
+                    cond.negate()
+                };
+                return (stmts, Some(cond));
+            }
+            OpCode::Exit => {}
+        }
+    }
+    (stmts, None)
+}
+
+#[derive(Debug)]
+struct NaturalLoop {
+    body: BTreeSet<usize>,
+    exit: Option<usize>,
+}
+
+#[derive(Clone, Copy)]
+struct LoopCtx<'a> {
+    header: usize,
+    exit: Option<usize>,
+    body: &'a BTreeSet<usize>,
+}
+
+struct Structurer<'a> {
+    cfg: &'a ControlFlowGraph,
+    dead_flags: BTreeSet<usize>,
+    ipdom: BTreeMap<usize, usize>,
+    loops: BTreeMap<usize, NaturalLoop>,
+}
+
+fn successors(block: &BasicBlock) -> Vec<usize> {
+    block
+        .successors
+        .iter()
+        .filter_map(|edge| match edge.target() {
+            Target::Static(address) => Some(address),
+            _ => None,
+        })
+        .collect()
+}
+
+impl<'a> Structurer<'a> {
+    fn new(cfg: &'a ControlFlowGraph) -> Option<Self> {
+        let has_indirect = cfg.blocks.values().any(|block| {
+            block
+                .successors
+                .iter()
+                .any(|edge| !matches!(edge.target(), Target::Static(_)))
+        });
+        if has_indirect {
+            return None;
+        }
+
+        Some(Self {
+            cfg,
+            dead_flags: Self::dead_flags(cfg),
+            ipdom: Self::post_dominators(cfg),
+            loops: Self::natural_loops(cfg)?,
+        })
+    }
+
+    /// Cells written by `LessThan`/`Equal` and only ever read by the jump
+    /// straight after, so the comparison can be folded into the condition.
+    fn dead_flags(cfg: &ControlFlowGraph) -> BTreeSet<usize> {
+        let mut flags = BTreeSet::new();
+        let mut reads = BTreeSet::new();
+        for block in cfg.blocks.values() {
+            for pair in block.instructions.windows(2) {
+                if matches!(pair[0].op, OpCode::LessThan | OpCode::Equal)
+                    && pair[1].op.is_jump()
+                    && pair[1].read_addresses().first() == pair[0].write_address().as_ref()
+                {
+                    flags.extend(pair[0].write_address());
+                }
+            }
+            for (i, instruction) in block.instructions.iter().enumerate() {
+                let mut read = instruction.read_addresses();
+                if instruction.op.is_jump() && i > 0 {
+                    let prev = &block.instructions[i - 1];
+                    if matches!(prev.op, OpCode::LessThan | OpCode::Equal) {
+                        read.retain(|address| Some(*address) != prev.write_address());
+                    }
+                }
+                reads.extend(read);
+            }
+        }
+        flags.difference(&reads).copied().collect()
+    }
+
+    fn post_dominators(cfg: &ControlFlowGraph) -> BTreeMap<usize, usize> {
+        let nodes: BTreeSet<usize> = cfg.blocks.keys().copied().chain([EXIT]).collect();
+        let succ = |node: usize| -> Vec<usize> {
+            let block = &cfg.blocks[&node];
+            let res = successors(block);
+            if res.is_empty() {
+                vec![EXIT]
+            } else {
+                res
+            }
+        };
+
+        let mut pdom: BTreeMap<usize, BTreeSet<usize>> =
+            nodes.iter().map(|&node| (node, nodes.clone())).collect();
+        pdom.insert(EXIT, BTreeSet::from([EXIT]));
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &node in cfg.blocks.keys().rev() {
+                let mut set = succ(node)
+                    .iter()
+                    .map(|s| {
+                        pdom.get(s)
+                            .cloned()
+                            .unwrap_or_else(|| BTreeSet::from([EXIT]))
+                    })
+                    .reduce(|a, b| a.intersection(&b).copied().collect())
+                    .unwrap_or_default();
+                set.insert(node);
+                if set != pdom[&node] {
+                    pdom.insert(node, set);
+                    changed = true;
+                }
+            }
+        }
+
+        pdom.iter()
+            .filter(|(&node, _)| node != EXIT)
+            .filter_map(|(&node, set)| {
+                let strict: BTreeSet<usize> = set.iter().copied().filter(|&d| d != node).collect();
+                strict
+                    .iter()
+                    .find(|d| pdom[d] == strict)
+                    .map(|&d| (node, d))
+            })
+            .collect()
+    }
+
+    fn natural_loops(cfg: &ControlFlowGraph) -> Option<BTreeMap<usize, NaturalLoop>> {
+        // back edges from a depth first search
+        let mut back_edges: Vec<(usize, usize)> = vec![];
+        let mut on_stack = BTreeSet::new();
+        let mut done = BTreeSet::new();
+        let mut stack = vec![(cfg.entry, 0)];
+        on_stack.insert(cfg.entry);
+        while let Some((node, i)) = stack.pop() {
+            let succ = cfg.blocks.get(&node).map(successors).unwrap_or_default();
+            if let Some(&next) = succ.get(i) {
+                stack.push((node, i + 1));
+                if on_stack.contains(&next) {
+                    back_edges.push((node, next));
+                } else if !done.contains(&next) && cfg.blocks.contains_key(&next) {
+                    on_stack.insert(next);
+                    stack.push((next, 0));
+                }
+            } else {
+                on_stack.remove(&node);
+                done.insert(node);
+            }
+        }
+
+        let mut loops: BTreeMap<usize, NaturalLoop> = BTreeMap::new();
+        for (latch, header) in back_edges {
+            let body = &mut loops
+                .entry(header)
+                .or_insert_with(|| NaturalLoop {
+                    body: BTreeSet::from([header]),
+                    exit: None,
+                })
+                .body;
+            let mut queue = vec![latch];
+            while let Some(node) = queue.pop() {
+                if body.insert(node) {
+                    queue.extend(cfg.blocks.values().filter_map(|block| {
+                        successors(block).contains(&node).then_some(block.start)
+                    }));
+                }
+            }
+        }
+
+        for natural_loop in loops.values_mut() {
+            let exits: BTreeSet<usize> = natural_loop
+                .body
+                .iter()
+                .flat_map(|node| successors(&cfg.blocks[node]))
+                .filter(|node| !natural_loop.body.contains(node))
+                .collect();
+            if exits.len() > 1 {
+                return None;
+            }
+            natural_loop.exit = exits.into_iter().next();
+        }
+        Some(loops)
+    }
+
+    fn structure(
+        &self,
+        mut node: usize,
+        stop: Option<usize>,
+        ctx: Option<LoopCtx>,
+        depth: usize,
+    ) -> Option<Vec<Stmt>> {
+        if depth > 2 * self.cfg.blocks.len() {
+            return None;
+        }
+        let mut stmts = vec![];
+        let mut seen = BTreeSet::new();
+        let mut entering = matches!(ctx, Some(ctx) if ctx.header == node);
+
+        loop {
+            if Some(node) == stop {
+                return Some(stmts);
+            }
+            if let Some(ctx) = ctx {
+                if node == ctx.header && !entering {
+                    stmts.push(Stmt::Continue);
+                    return Some(stmts);
+                }
+                if Some(node) == ctx.exit {
+                    stmts.push(Stmt::Break);
+                    return Some(stmts);
+                }
+                if !ctx.body.contains(&node) {
+                    return None;
+                }
+            }
+            if !seen.insert(node) {
+                return None;
+            }
+
+            if let Some(natural_loop) = self.loops.get(&node) {
+                if !entering {
+                    let inner = LoopCtx {
+                        header: node,
+                        exit: natural_loop.exit,
+                        body: &natural_loop.body,
+                    };
+                    let body = self.structure(node, None, Some(inner), depth + 1)?;
+                    stmts.push(make_loop(body));
+                    match natural_loop.exit {
+                        Some(exit) => {
+                            node = exit;
+                            continue;
+                        }
+                        None => return Some(stmts),
+                    }
+                }
+            }
+            entering = false;
+
+            let Some(block) = self
+                .cfg
+                .blocks
+                .get(&node)
+                .filter(|block| !block.is_invalid())
+            else {
+                stmts.push(Stmt::Invalid(node));
+                return Some(stmts);
+            };
+            let (lifted, cond) = lift_block(block, &self.dead_flags);
+            stmts.extend(lifted);
+
+            match (block.successors.as_slice(), cond) {
+                (_, _) if block.halts() => {
+                    stmts.push(Stmt::Return);
+                    return Some(stmts);
+                }
+                ([], _) => {
+                    stmts.push(Stmt::Invalid(block.end()));
+                    return Some(stmts);
+                }
+                ([edge], _) => {
+                    node = match edge.target() {
+                        Target::Static(address) => address,
+                        _ => return None,
+                    };
+                }
+                ([taken, fallthrough], Some(cond)) => {
+                    let (Target::Static(taken), Target::Static(fallthrough)) =
+                        (taken.target(), fallthrough.target())
+                    else {
+                        return None;
+                    };
+                    let join = self
+                        .ipdom
+                        .get(&node)
+                        .copied()
+                        .filter(|&join| join != EXIT)
+                        .filter(|join| !matches!(ctx, Some(ctx) if !ctx.body.contains(join)))
+                        .filter(|join| Some(*join) != ctx.map(|ctx| ctx.header));
+                    let inner_stop = join.or(stop);
+                    let then = self.structure(taken, inner_stop, ctx, depth + 1)?;
+                    let otherwise = self.structure(fallthrough, inner_stop, ctx, depth + 1)?;
+                    stmts.push(make_if(cond, then, otherwise));
+                    match join {
+                        Some(join) => node = join,
+                        None => return Some(stmts),
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn make_if(cond: Cond, then: Vec<Stmt>, otherwise: Vec<Stmt>) -> Stmt {
+    if then.is_empty() {
+        Stmt::If(cond.negate(), otherwise, then)
+    } else {
+        Stmt::If(cond, then, otherwise)
+    }
+}
+
+/// Turns `loop { if c { break; } else { .. } }` into `while !c { .. }`.
+fn make_loop(mut body: Vec<Stmt>) -> Stmt {
+    if body.last() == Some(&Stmt::Continue) {
+        body.pop();
+    }
+    if let [Stmt::If(cond, then, otherwise)] = body.as_slice() {
+        let mut rest = None;
+        if then == &[Stmt::Break] {
+            rest = Some((cond.negate(), otherwise.clone()));
+        } else if otherwise == &[Stmt::Break] {
+            rest = Some((cond.clone(), then.clone()));
+        }
+        if let Some((cond, mut rest)) = rest {
+            if rest.last() == Some(&Stmt::Continue) {
+                rest.pop();
+            }
+            return Stmt::While(cond, rest);
+        }
+    }
+    Stmt::Loop(body)
+}
+
+/// One `pc` arm per basic block, used when the graph can't be structured.
+fn dispatch(cfg: &ControlFlowGraph) -> Vec<Stmt> {
+    let arms = cfg
+        .blocks
+        .values()
+        .map(|block| {
+            if block.is_invalid() {
+                return (block.start, vec![Stmt::Invalid(block.start)]);
+            }
+            let (mut stmts, cond) = lift_block(block, &BTreeSet::new());
+            if block.halts() {
+                stmts.push(Stmt::Return);
+            } else {
+                match block.successors.as_slice() {
+                    [] => stmts.push(Stmt::Invalid(block.end())),
+                    [edge] => stmts.push(Stmt::Goto(None, edge.target(), None)),
+                    [taken, Edge::Fallthrough(next)] => {
+                        stmts.push(Stmt::Goto(cond, taken.target(), Some(*next)))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            (block.start, stmts)
+        })
+        .collect();
+    vec![Stmt::Dispatch(arms)]
+}
+
+impl Decompiled {
+    pub fn new<M: Cells + ?Sized>(memory: &M) -> Self {
+        let cfg = ControlFlowGraph::new(memory, 0);
+        let structured = Structurer::new(&cfg)
+            .and_then(|structurer| structurer.structure(cfg.entry, None, None, 0));
+
+        let (body, structured) = match structured {
+            Some(mut body) => {
+                if body.last() == Some(&Stmt::Return) {
+                    body.pop();
+                }
+                (body, true)
+            }
+            None => (dispatch(&cfg), false),
+        };
+
+        let mut vars = BTreeSet::new();
+        body.iter().for_each(|stmt| stmt.vars(&mut vars));
+        let variables = vars
+            .into_iter()
+            .map(|address| (address, memory.cell(address).unwrap_or(0)))
+            .collect();
+
+        let relative_base = cfg
+            .blocks
+            .values()
+            .flat_map(|block| &block.instructions)
+            .any(|instruction| {
+                instruction.op == OpCode::AdjustBase
+                    || instruction.modes[..instruction.params.len()]
+                        .contains(&ParameterMode::Relative)
+            });
+
+        let memory = if relative_base {
+            (0..memory.len())
+                .map(|address| memory.cell(address).unwrap())
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Self {
+            variables,
+            relative_base,
+            memory,
+            body,
+            structured,
+        }
+    }
+}
+
+/// The relative-mode cell at `offset`, e.g. `mem[(rb - 1) as usize]`.
+fn relative_cell(offset: i32) -> String {
+    match offset {
+        0 => "mem[rb as usize]".to_string(),
+        offset if offset < 0 => format!("mem[(rb - {}) as usize]", -(offset as i64)),
+        offset => format!("mem[(rb + {offset}) as usize]"),
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Literal(value) => write!(f, "{value}"),
+            Expr::Var(address) => write!(f, "m{address}"),
+            Expr::Relative(offset) => write!(f, "{}", relative_cell(*offset)),
+            Expr::Input => write!(f, "input()"),
+            Expr::Add(a, b) => write!(f, "{a} + {b}"),
+            Expr::Mul(a, b) => write!(f, "{a} * {b}"),
+            Expr::Flag(cond) => write!(f, "({cond}) as i32"),
+        }
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            CmpOp::Lt => "<",
+            CmpOp::Ge => ">=",
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+        };
+        write!(f, "{} {op} {}", self.lhs, self.rhs)
+    }
+}
+
+fn write_block(f: &mut fmt::Formatter, stmts: &[Stmt], indent: usize) -> fmt::Result {
+    for stmt in stmts {
+        write_stmt(f, stmt, indent)?;
+    }
+    Ok(())
+}
+
+fn write_stmt(f: &mut fmt::Formatter, stmt: &Stmt, indent: usize) -> fmt::Result {
+    let pad = "    ".repeat(indent);
+    match stmt {
+        Stmt::Assign(to, expr) => writeln!(f, "{pad}{to} = {expr};"),
+        Stmt::AdjustBase(expr) => writeln!(f, "{pad}rb += {expr};"),
+        Stmt::Output(expr) => writeln!(f, "{pad}output({expr});"),
+        Stmt::If(cond, then, otherwise) => {
+            writeln!(f, "{pad}if {cond} {{")?;
+            write_block(f, then, indent + 1)?;
+            if !otherwise.is_empty() {
+                writeln!(f, "{pad}}} else {{")?;
+                write_block(f, otherwise, indent + 1)?;
+            }
+            writeln!(f, "{pad}}}")
+        }
+        Stmt::Loop(body) => {
+            writeln!(f, "{pad}loop {{")?;
+            write_block(f, body, indent + 1)?;
+            writeln!(f, "{pad}}}")
+        }
+        Stmt::While(cond, body) => {
+            writeln!(f, "{pad}while {cond} {{")?;
+            write_block(f, body, indent + 1)?;
+            writeln!(f, "{pad}}}")
+        }
+        Stmt::Break => writeln!(f, "{pad}break;"),
+        Stmt::Continue => writeln!(f, "{pad}continue;"),
+        Stmt::Return => writeln!(f, "{pad}return;"),
+        Stmt::Invalid(address) => {
+            writeln!(
+                f,
+                "{pad}unreachable!(\"invalid instruction at {address}\");"
+            )
+        }
+        Stmt::Dispatch(arms) => {
+            writeln!(f, "{pad}let mut pc = 0;")?;
+            writeln!(f, "{pad}loop {{")?;
+            writeln!(f, "{pad}    match pc {{")?;
+            for (address, body) in arms {
+                writeln!(f, "{pad}        {address} => {{")?;
+                write_block(f, body, indent + 3)?;
+                writeln!(f, "{pad}        }}")?;
+            }
+            writeln!(f, "{pad}        _ => unreachable!(),")?;
+            writeln!(f, "{pad}    }}")?;
+            writeln!(f, "{pad}}}")
+        }
+        Stmt::Goto(cond, target, otherwise) => {
+            let target = match target {
+                Target::Static(address) => address.to_string(),
+                Target::Indirect(address) => format!("m{address} as usize"),
+                Target::Relative(offset) => format!("{} as usize", relative_cell(*offset)),
+            };
+            match (cond, otherwise) {
+                (Some(cond), Some(otherwise)) => {
+                    writeln!(
+                        f,
+                        "{pad}pc = if {cond} {{ {target} }} else {{ {otherwise} }};"
+                    )
+                }
+                _ => writeln!(f, "{pad}pc = {target};"),
+            }
+        }
+    }
+}
+
+impl fmt::Display for Decompiled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "fn program() {{")?;
+        for (address, value) in &self.variables {
+            writeln!(f, "    let mut m{address} = {value};")?;
+        }
+        if self.relative_base {
+            writeln!(f, "    let mut mem: Vec<i32> = vec!{:?};", self.memory)?;
+            writeln!(f, "    let mut rb: i32 = 0;")?;
+        }
+        write_block(f, &self.body, 1)?;
+        writeln!(f, "}}")
+    }
+}
+
+impl Computer {
+    pub fn decompile(&self) -> Decompiled {
+        Decompiled::new(&self.memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_straight_line() {
+        let computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!(
+            computer.decompile().to_string(),
+            "fn program() {
+    let mut m9 = -1;
+    let mut m10 = 8;
+    m9 = input();
+    m9 = (m9 == m10) as i32;
+    output(m9);
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_if_else() {
+        let larger_program = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        let decompiled = Computer::from(larger_program).decompile();
+        assert!(decompiled.structured);
+        assert_eq!(
+            decompiled.to_string(),
+            "fn program() {
+    let mut m20 = 0;
+    let mut m21 = 0;
+    m21 = input();
+    m20 = (m21 == 8) as i32;
+    if m20 != 0 {
+        m20 = m21 * 125;
+        output(m20);
+    } else {
+        m20 = (8 < m21) as i32;
+        if m20 == 0 {
+            output(999);
+        } else {
+            m20 = 1000 + 1;
+            output(m20);
+        }
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_flag_overwrites_operand() {
+        // m20 = (m20 == m21), then jumps on the new m20
+        let program = "3,20,8,20,21,20,1005,20,13,4,20,99,0,104,1,99,0,0,0,0,-1,8";
+        let decompiled = Computer::from(program).decompile();
+        assert_eq!(
+            decompiled.to_string(),
+            "fn program() {
+    let mut m20 = -1;
+    let mut m21 = 8;
+    m20 = input();
+    m20 = (m20 == m21) as i32;
+    if m20 != 0 {
+        output(1);
+        return;
+    } else {
+        output(m20);
+        return;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_while() {
+        // counts m15 down from the input, printing each value
+        let program = "3,15,1006,15,14,4,15,1001,15,-1,15,1105,1,2,99,0";
+        let decompiled = Computer::from(program).decompile();
+        assert!(decompiled.structured);
+        assert_eq!(
+            decompiled.to_string(),
+            "fn program() {
+    let mut m15 = 0;
+    m15 = input();
+    while m15 != 0 {
+        output(m15);
+        m15 = m15 + -1;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_relative() {
+        let decompiled = Computer::from("109,5,21101,1,2,-1,204,-1,99").decompile();
+        assert_eq!(
+            decompiled.to_string(),
+            "fn program() {
+    let mut mem: Vec<i32> = vec![109, 5, 21101, 1, 2, -1, 204, -1, 99];
+    let mut rb: i32 = 0;
+    rb += 5;
+    mem[(rb - 1) as usize] = 1 + 2;
+    output(mem[(rb - 1) as usize]);
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_indirect_falls_back_to_dispatch() {
+        let decompiled = Computer::from("3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9").decompile();
+        assert!(!decompiled.structured);
+        let source = decompiled.to_string();
+        assert!(source.contains("pc = if m12 == 0 { m15 as usize } else { 5 };"));
+        assert!(source.contains("        5 => {\n"));
+    }
+}
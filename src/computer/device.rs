@@ -0,0 +1,299 @@
+use std::any::Any;
+use std::fmt;
+
+use super::{Computer, ComputerError};
+
+/// Hardware attached to a range of addresses. Operand reads and writes in the
+/// range go to the device instead of memory, with `offset` relative to the
+/// start of the range. Instructions are always fetched from memory.
+pub trait Device: DeviceClone + fmt::Debug + Send + Sync {
+    fn read(&mut self, offset: usize) -> i32;
+    fn write(&mut self, offset: usize, value: i32);
+    /// Called before every instruction.
+    fn tick(&mut self) {}
+    /// Number of addresses the device can back, or `None` if it takes any
+    /// offset.
+    fn size(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Lets `Computer` stay `Clone`; implemented for every `Clone` device.
+pub trait DeviceClone {
+    fn clone_box(&self) -> Box<dyn Device>;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Device + Clone + 'static> DeviceClone for T {
+    fn clone_box(&self) -> Box<dyn Device> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Device> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Mapping {
+    pub start: usize,
+    pub len: usize,
+    pub device: Box<dyn Device>,
+}
+
+impl Mapping {
+    fn contains(&self, address: usize) -> bool {
+        (self.start..self.start + self.len).contains(&address)
+    }
+}
+
+/// Counts instructions run since it was mapped. Writing sets the count.
+#[derive(Debug, Clone, Default)]
+pub struct CycleCounter {
+    pub cycles: i32,
+}
+
+impl Device for CycleCounter {
+    fn read(&mut self, _offset: usize) -> i32 {
+        self.cycles
+    }
+
+    fn write(&mut self, _offset: usize, value: i32) {
+        self.cycles = value;
+    }
+
+    fn tick(&mut self) {
+        self.cycles = self.cycles.wrapping_add(1);
+    }
+}
+
+/// Every read returns the next non-negative value of a seeded xorshift
+/// generator, so runs are reproducible. Writing reseeds it.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero.
+        Self { state: seed.max(1) }
+    }
+}
+
+impl Device for Random {
+    fn read(&mut self, _offset: usize) -> i32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 33) as i32
+    }
+
+    fn write(&mut self, _offset: usize, value: i32) {
+        *self = Self::new(value as u64);
+    }
+}
+
+/// A `width` by `height` grid of cells, stored row by row.
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<i32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    /// One line per row. Zero renders as `.`, printable ASCII as itself and
+    /// anything else as `#`. Empty if `width` is zero.
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+        if self.width == 0 {
+            return res;
+        }
+        for row in self.cells.chunks(self.width) {
+            for &cell in row {
+                res.push(match cell {
+                    0 => '.',
+                    32..=126 => cell as u8 as char,
+                    _ => '#',
+                });
+            }
+            res.push('\n');
+        }
+        res
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> i32 {
+        self.cells[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: i32) {
+        self.cells[offset] = value;
+    }
+
+    fn size(&self) -> Option<usize> {
+        Some(self.cells.len())
+    }
+}
+
+impl Computer {
+    /// Attaches `device` to `len` addresses from `start`. The range may lie
+    /// past the end of memory but can't overlap another device or be larger
+    /// than the device's `size`.
+    pub fn map_device<D: Device + 'static>(
+        &mut self,
+        start: usize,
+        len: usize,
+        device: D,
+    ) -> Result<(), ComputerError> {
+        let end = start
+            .checked_add(len)
+            .ok_or(ComputerError::DeviceOverflow { start, len })?;
+        if let Some(size) = device.size().filter(|&size| len > size) {
+            return Err(ComputerError::DeviceTooSmall { len, size });
+        }
+        if let Some(other) = self
+            .devices
+            .iter()
+            .find(|other| start < other.start + other.len && other.start < end)
+        {
+            return Err(ComputerError::DeviceOverlap {
+                start,
+                end,
+                other_start: other.start,
+                other_end: other.start + other.len,
+            });
+        }
+        self.devices.push(Mapping {
+            start,
+            len,
+            device: Box::new(device),
+        });
+        Ok(())
+    }
+
+    /// The device mapped at `start`, if it has type `D`.
+    pub fn device<D: Device + 'static>(&self, start: usize) -> Option<&D> {
+        self.devices
+            .iter()
+            .find(|mapping| mapping.start == start)
+            .and_then(|mapping| mapping.device.as_any().downcast_ref())
+    }
+
+    pub(super) fn mapping(&self, address: usize) -> Option<usize> {
+        self.devices
+            .iter()
+            .position(|mapping| mapping.contains(address))
+    }
+
+    /// Reads an operand, going through a device if one is mapped at
+    /// `address`. Custom instructions should use this over indexing memory.
+    pub fn read(&mut self, address: usize) -> i32 {
+        match self.mapping(address) {
+            Some(index) => {
+                let mapping = &mut self.devices[index];
+                mapping.device.read(address - mapping.start)
+            }
+            None => self.memory[address],
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: i32) {
+        match self.mapping(address) {
+            Some(index) => {
+                let mapping = &mut self.devices[index];
+                mapping.device.write(address - mapping.start, value)
+            }
+            None => self.memory[address] = value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Isa;
+
+    #[test]
+    fn test_devices() {
+        // Copies the cycle count and two random numbers to memory.
+        let mut computer = Computer::from("1001,100,0,13,1001,101,0,14,1001,101,0,15,99,0,0,0");
+        computer.isa = Isa::Day5;
+        computer
+            .map_device(100, 1, CycleCounter::default())
+            .unwrap();
+        computer.map_device(101, 1, Random::new(42)).unwrap();
+        assert_eq!(
+            computer.map_device(100, 2, Random::new(1)),
+            Err(ComputerError::DeviceOverlap {
+                start: 100,
+                end: 102,
+                other_start: 100,
+                other_end: 101
+            })
+        );
+        assert_eq!(
+            computer.map_device(usize::MAX, 2, Random::new(1)),
+            Err(ComputerError::DeviceOverflow {
+                start: usize::MAX,
+                len: 2
+            })
+        );
+
+        let mut fork = computer.clone();
+        computer.run_program().unwrap();
+        assert_eq!(computer.memory[13], 1);
+        assert_ne!(computer.memory[14], computer.memory[15]);
+        assert!(computer.memory[14] >= 0);
+        assert_eq!(computer.device::<CycleCounter>(100).unwrap().cycles, 4);
+
+        fork.run_program().unwrap();
+        assert_eq!(fork.memory, computer.memory);
+
+        // Parameters past the end of memory aren't read from a device.
+        let mut computer = Computer::from("1,0,0");
+        computer.map_device(3, 2, CycleCounter::default()).unwrap();
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::OutOfBounds {
+                pointer: 0,
+                address: 3,
+                size: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_framebuffer() {
+        // Draws a diagonal of '#' and an 'A'.
+        let mut computer = Computer::from("1101,0,1,200,1101,0,1,203,1101,0,65,202,99");
+        assert_eq!(
+            computer.map_device(10, 5, Framebuffer::new(1, 1)),
+            Err(ComputerError::DeviceTooSmall { len: 5, size: 1 })
+        );
+        computer.map_device(200, 4, Framebuffer::new(2, 2)).unwrap();
+        computer.run_program().unwrap();
+        assert_eq!(computer.len(), 13);
+        assert_eq!(
+            computer.device::<Framebuffer>(200).unwrap().render(),
+            "#.\nA#\n"
+        );
+        assert_eq!(Framebuffer::new(0, 3).render(), "");
+    }
+}
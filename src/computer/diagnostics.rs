@@ -0,0 +1,131 @@
+use std::fmt;
+
+use super::Computer;
+
+/// One self-test output. Zero means the test passed, anything else is how far
+/// off the result was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestResult {
+    pub index: usize,
+    /// Pointer of the `Output` instruction, if it was recorded.
+    pub pointer: Option<usize>,
+    pub value: i32,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.value == 0
+    }
+}
+
+/// Outputs of a diagnostic program: every output but the last is a test
+/// result, the last one is the diagnostic code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticReport {
+    pub tests: Vec<TestResult>,
+    /// `None` if the program produced no output at all.
+    pub code: Option<i32>,
+}
+
+impl DiagnosticReport {
+    pub fn new(output: &[i32], pointers: &[usize]) -> Self {
+        let Some((&code, tests)) = output.split_last() else {
+            return Self {
+                tests: vec![],
+                code: None,
+            };
+        };
+        let tests = tests
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| TestResult {
+                index,
+                pointer: pointers.get(index).copied(),
+                value,
+            })
+            .collect();
+        Self {
+            tests,
+            code: Some(code),
+        }
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.tests.iter().filter(|test| !test.passed())
+    }
+
+    /// True when every test passed and a diagnostic code was produced.
+    pub fn passed(&self) -> bool {
+        self.code.is_some() && self.failures().next().is_none()
+    }
+
+    /// The diagnostic code, or the report itself if anything failed.
+    pub fn result(&self) -> Result<i32, &Self> {
+        match self.code {
+            Some(code) if self.passed() => Ok(code),
+            _ => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for test in &self.tests {
+            let status = if test.passed() { "pass" } else { "FAIL" };
+            write!(f, "test {}: {status}", test.index)?;
+            if let Some(pointer) = test.pointer {
+                write!(f, " at {pointer}")?;
+            }
+            if !test.passed() {
+                write!(f, " (off by {})", test.value)?;
+            }
+            writeln!(f)?;
+        }
+        match self.code {
+            Some(code) => writeln!(f, "diagnostic code: {code}"),
+            None => writeln!(f, "no output"),
+        }
+    }
+}
+
+impl Computer {
+    pub fn diagnostic_report(&self) -> DiagnosticReport {
+        DiagnosticReport::new(&self.output, &self.output_pointers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut computer = Computer::from("104,0,104,3,104,0,104,42,99");
+        computer.run_program().expect("Program failed");
+        let report = computer.diagnostic_report();
+        assert_eq!(report.code, Some(42));
+        assert_eq!(
+            report.failures().collect::<Vec<_>>(),
+            vec![&TestResult {
+                index: 1,
+                pointer: Some(2),
+                value: 3
+            }]
+        );
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "test 0: pass at 0\ntest 1: FAIL at 2 (off by 3)\ntest 2: pass at 4\ndiagnostic code: 42\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_output() {
+        let computer = Computer::from("99");
+        let report = computer.diagnostic_report();
+        assert_eq!(report.code, None);
+        assert!(!report.passed());
+        assert!(report.result().is_err());
+        assert_eq!(computer.check_diagnostics(), Err(&[][..]));
+    }
+}
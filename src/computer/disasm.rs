@@ -0,0 +1,199 @@
+use std::fmt;
+
+use super::memory::Cells;
+use super::{Digits, Modes, OpCode, ParameterMode};
+
+/// A single decoded instruction, including its raw parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instruction {
+    pub address: usize,
+    pub op: OpCode,
+    pub modes: Modes,
+    pub params: Vec<i32>,
+}
+
+/// Why an address doesn't hold a valid instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    OutOfMemory,
+    UnknownOpCode(i32),
+    InvalidMode(i32),
+    /// The parameters run past the end of memory.
+    Truncated,
+}
+
+impl Instruction {
+    /// Decodes the instruction at `address` without running it. Returns `None`
+    /// for unknown opcodes, unknown parameter modes or instructions that run
+    /// past the end of memory.
+    pub fn decode<M: Cells + ?Sized>(memory: &M, address: usize) -> Option<Self> {
+        Self::try_decode(memory, address).ok()
+    }
+
+    pub fn try_decode<M: Cells + ?Sized>(memory: &M, address: usize) -> Result<Self, DecodeError> {
+        let value = memory.cell(address).ok_or(DecodeError::OutOfMemory)?;
+        if value < 0 {
+            return Err(DecodeError::UnknownOpCode(value));
+        }
+        let command = value as u32;
+        let op = OpCode::decode((command.digit(1) * 10) + command.digit(0))
+            .ok_or(DecodeError::UnknownOpCode(value))?;
+        let mode = |digit| {
+            ParameterMode::decode(command.digit(digit)).ok_or(DecodeError::InvalidMode(value))
+        };
+        let modes = [mode(2)?, mode(3)?, mode(4)?];
+        let params = (address + 1..address + 1 + op.arity())
+            .map(|address| memory.cell(address))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(DecodeError::Truncated)?;
+
+        Ok(Self {
+            address,
+            op,
+            modes,
+            params,
+        })
+    }
+
+    /// Number of memory cells taken up by the instruction.
+    pub fn size(&self) -> usize {
+        1 + self.params.len()
+    }
+
+    /// Address of the instruction that follows this one in memory.
+    pub fn next(&self) -> usize {
+        self.address + self.size()
+    }
+
+    /// Address written by the instruction, if any. Immediate-mode destinations
+    /// resolve to the parameter's own cell, matching `Computer::get_pos`.
+    pub fn write_address(&self) -> Option<usize> {
+        self.operand_address(self.destination()?)
+    }
+
+    /// Index of the parameter the instruction writes to, if any.
+    pub fn destination(&self) -> Option<usize> {
+        match self.op {
+            OpCode::Add | OpCode::Mul | OpCode::LessThan | OpCode::Equal => Some(2),
+            OpCode::Input => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Addresses read through position-mode parameters, excluding the
+    /// destination.
+    pub fn read_addresses(&self) -> Vec<usize> {
+        let reads = match self.op {
+            OpCode::Add | OpCode::Mul | OpCode::LessThan | OpCode::Equal => 2,
+            OpCode::JumpTrue | OpCode::JumpFalse => 2,
+            OpCode::Output | OpCode::AdjustBase => 1,
+            OpCode::Input | OpCode::Exit => 0,
+        };
+        (0..reads)
+            .filter(|&param| self.modes[param] == ParameterMode::Position)
+            .filter_map(|param| self.operand_address(param))
+            .collect()
+    }
+
+    /// `None` for relative-mode parameters, which depend on the runtime base.
+    fn operand_address(&self, param: usize) -> Option<usize> {
+        match self.modes[param] {
+            ParameterMode::Position => Some(self.params[param] as usize),
+            ParameterMode::Immediate => Some(self.address + 1 + param),
+            ParameterMode::Relative => None,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self.op {
+            OpCode::Add => "ADD",
+            OpCode::Mul => "MUL",
+            OpCode::Input => "IN",
+            OpCode::Output => "OUT",
+            OpCode::JumpTrue => "JT",
+            OpCode::JumpFalse => "JF",
+            OpCode::LessThan => "LT",
+            OpCode::Equal => "EQ",
+            OpCode::AdjustBase => "ARB",
+            OpCode::Exit => "HALT",
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}: {}", self.address, self.mnemonic())?;
+        for (i, (param, mode)) in self.params.iter().zip(&self.modes).enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            match mode {
+                ParameterMode::Position => write!(f, "{sep}[{param}]")?,
+                ParameterMode::Immediate => write!(f, "{sep}{param}")?,
+                ParameterMode::Relative => write!(f, "{sep}[{}]", relative(*param))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `rb+offset`, the address of a relative-mode operand.
+pub fn relative(offset: i32) -> String {
+    if offset < 0 {
+        format!("rb{offset}")
+    } else {
+        format!("rb+{offset}")
+    }
+}
+
+/// Linear disassembly of `memory` from `start`, stopping at the first address
+/// that doesn't decode.
+pub fn disassemble<M: Cells + ?Sized>(memory: &M, start: usize) -> Vec<Instruction> {
+    let mut res = vec![];
+    let mut address = start;
+    while let Some(instruction) = Instruction::decode(memory, address) {
+        address = instruction.next();
+        res.push(instruction);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Computer;
+
+    #[test]
+    fn test_decode() {
+        let computer = Computer::from("1002,4,3,4,33");
+        let instruction = Instruction::decode(&computer.memory, 0).unwrap();
+        assert_eq!(instruction.op, OpCode::Mul);
+        assert_eq!(instruction.params, vec![4, 3, 4]);
+        assert_eq!(instruction.next(), 4);
+        assert_eq!(instruction.to_string(), "0000: MUL [4], 3, [4]");
+        assert_eq!(instruction.write_address(), Some(4));
+        assert_eq!(instruction.read_addresses(), vec![4]);
+
+        assert_eq!(Instruction::decode(&computer.memory, 4), None);
+        assert_eq!(Instruction::decode(&[1, 0, 0], 0), None);
+        assert_eq!(Instruction::decode(&[-1], 0), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let computer = Computer::from("3,0,4,0,99");
+        let listing = disassemble(&computer.memory, 0)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(listing, vec!["0000: IN [0]", "0002: OUT [0]", "0004: HALT"]);
+
+        let computer = Computer::from("109,1,204,-1,99");
+        let listing = disassemble(&computer.memory, 0)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            listing,
+            vec!["0000: ARB 1", "0002: OUT [rb-1]", "0004: HALT"]
+        );
+    }
+}
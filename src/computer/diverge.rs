@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::ControlFlow;
+
+use super::disasm::Instruction;
+use super::{Computer, ComputerError, ParameterMode};
+
+/// Number of steps kept before the divergence, including the differing one.
+const CONTEXT: usize = 5;
+
+/// What one machine did in one step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    pub step: usize,
+    pub pointer: usize,
+    /// `None` if the pointer doesn't hold a built-in instruction.
+    pub instruction: Option<Instruction>,
+    /// `(address, value)` written by the instruction.
+    pub write: Option<(usize, i32)>,
+    pub output: Option<i32>,
+    /// Set if the machine stopped in this step.
+    pub halt: Option<Result<String, ComputerError>>,
+}
+
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}  ", self.step)?;
+        match &self.instruction {
+            Some(instruction) => write!(f, "{instruction}")?,
+            None => write!(f, "{:04}: <invalid>", self.pointer)?,
+        }
+        if let Some((address, value)) = self.write {
+            write!(f, "  [{address}] = {value}")?;
+        }
+        if let Some(output) = self.output {
+            write!(f, "  out {output}")?;
+        }
+        match &self.halt {
+            Some(Ok(_)) => write!(f, "  halt"),
+            Some(Err(err)) => write!(f, "  error: {err}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    Pointer,
+    Instruction,
+    Write,
+    Output,
+    /// One machine stopped and the other didn't, or they stopped differently.
+    Halt,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub step: usize,
+    pub difference: Difference,
+    /// The last few steps of each machine, ending with the differing one.
+    pub left: Vec<TraceStep>,
+    pub right: Vec<TraceStep>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "diverged at step {}: {:?}", self.step, self.difference)?;
+        for (name, trace) in [("left", &self.left), ("right", &self.right)] {
+            writeln!(f, "{name}:")?;
+            for step in trace {
+                writeln!(f, "  {step}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Address written by `instruction` when run on `computer`, resolving
+/// relative-mode destinations against the current base.
+fn write_address(computer: &Computer, instruction: &Instruction) -> Option<usize> {
+    let param = instruction.destination()?;
+    match instruction.modes[param] {
+        ParameterMode::Relative => {
+            usize::try_from(computer.relative_base as i64 + instruction.params[param] as i64).ok()
+        }
+        _ => instruction.write_address(),
+    }
+}
+
+/// Runs one instruction, recording what it did.
+pub(super) fn step(computer: &mut Computer, step: usize) -> TraceStep {
+    let pointer = computer.pointer;
+    let instruction = Instruction::decode(&computer.memory, pointer);
+    let address = instruction
+        .as_ref()
+        .and_then(|instruction| write_address(computer, instruction));
+    let outputs = computer.output.len();
+    let halt = match computer.run_command() {
+        ControlFlow::Break(res) => Some(res),
+        ControlFlow::Continue(_) => None,
+    };
+    let write = match halt {
+        Some(_) => None,
+        None => address.and_then(|address| Some((address, *computer.memory.get(address)?))),
+    };
+    TraceStep {
+        step,
+        pointer,
+        instruction,
+        write,
+        output: computer.output.get(outputs).copied(),
+        halt,
+    }
+}
+
+fn compare(left: &TraceStep, right: &TraceStep) -> Option<Difference> {
+    if left.pointer != right.pointer {
+        Some(Difference::Pointer)
+    } else if left.instruction != right.instruction {
+        Some(Difference::Instruction)
+    } else if left.halt != right.halt {
+        Some(Difference::Halt)
+    } else if left.write != right.write {
+        Some(Difference::Write)
+    } else if left.output != right.output {
+        Some(Difference::Output)
+    } else {
+        None
+    }
+}
+
+/// Runs clones of `left` and `right` in lockstep for at most `max_steps`
+/// instructions. Returns `None` if both stop the same way at the same step,
+/// or neither diverges within the limit.
+pub fn diverge(left: &Computer, right: &Computer, max_steps: usize) -> Option<Divergence> {
+    let mut machines = [left.clone(), right.clone()];
+    let mut traces = [VecDeque::new(), VecDeque::new()];
+
+    for index in 0..max_steps {
+        let [left, right] = &mut machines;
+        let steps = [step(left, index), step(right, index)];
+        let difference = compare(&steps[0], &steps[1]);
+        let halted = steps[0].halt.is_some();
+        for (trace, step) in traces.iter_mut().zip(steps) {
+            if trace.len() == CONTEXT {
+                trace.pop_front();
+            }
+            trace.push_back(step);
+        }
+        if let Some(difference) = difference {
+            let [left, right] = traces.map(Vec::from);
+            return Some(Divergence {
+                step: index,
+                difference,
+                left,
+                right,
+            });
+        }
+        if halted {
+            break;
+        }
+    }
+    None
+}
+
+impl Computer {
+    pub fn diverge(&self, other: &Computer, max_steps: usize) -> Option<Divergence> {
+        diverge(self, other, max_steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Arithmetic;
+
+    #[test]
+    fn test_diverge() {
+        let computer = Computer::from("1,5,5,0,99,1");
+        assert_eq!(computer.diverge(&computer, 100), None);
+
+        let divergence = computer
+            .diverge(&Computer::from("1,5,5,0,99,2"), 100)
+            .unwrap();
+        assert_eq!(divergence.step, 0);
+        assert_eq!(divergence.difference, Difference::Write);
+        assert_eq!(divergence.left[0].write, Some((0, 2)));
+        assert_eq!(divergence.right[0].write, Some((0, 4)));
+
+        let divergence = Computer::from("1005,5,4,99,99,0")
+            .diverge(&Computer::from("1005,5,4,99,99,1"), 100)
+            .unwrap();
+        assert_eq!(divergence.step, 1);
+        assert_eq!(divergence.difference, Difference::Pointer);
+        assert_eq!(
+            divergence.to_string(),
+            "diverged at step 1: Pointer\n\
+             left:\n  #0  0000: JT [5], 4\n  #1  0003: HALT  halt\n\
+             right:\n  #0  0000: JT [5], 4\n  #1  0004: HALT  halt\n"
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let checked = Computer::from("1002,5,2,0,99,2147483647");
+        let mut wrapping = checked.clone();
+        wrapping.arithmetic = Arithmetic::Wrapping;
+        let divergence = checked.diverge(&wrapping, 100).unwrap();
+        assert_eq!(divergence.difference, Difference::Halt);
+        assert!(matches!(
+            divergence.left[0].halt,
+            Some(Err(ComputerError::Overflow { .. }))
+        ));
+
+        let divergence = Computer::from("4,3,99,7")
+            .diverge(&Computer::from("4,3,99,8"), 100)
+            .unwrap();
+        assert_eq!(divergence.difference, Difference::Output);
+    }
+}
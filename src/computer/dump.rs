@@ -0,0 +1,299 @@
+use std::fmt;
+use std::fs;
+
+use super::classify::{CellKind, Classification};
+use super::disasm::Instruction;
+use super::memory::Cells;
+use super::{Computer, Memory};
+
+/// Hexdump-style rendering of a memory image, `width` cells per row with the
+/// address of the first cell in front. Decimal unless `hex` is set.
+#[derive(Debug, Clone)]
+pub struct Dump<'a, M: ?Sized = Memory> {
+    memory: &'a M,
+    width: usize,
+    hex: bool,
+    annotations: Option<&'a Classification>,
+}
+
+impl<'a, M: Cells + ?Sized> Dump<'a, M> {
+    pub fn new(memory: &'a M) -> Self {
+        Self {
+            memory,
+            width: 8,
+            hex: false,
+            annotations: None,
+        }
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width.max(1);
+        self
+    }
+
+    /// Addresses and cells in hexadecimal, negative cells as two's
+    /// complement.
+    pub fn hex(mut self) -> Self {
+        self.hex = true;
+        self
+    }
+
+    fn format(&self, value: i32) -> String {
+        if self.hex {
+            format!("{value:x}")
+        } else {
+            value.to_string()
+        }
+    }
+
+    /// Follows each row with the disassembly of the code cells starting in
+    /// it, according to `classification`.
+    pub fn annotated(mut self, classification: &'a Classification) -> Self {
+        self.annotations = Some(classification);
+        self
+    }
+
+    fn annotation(&self, classification: &Classification, start: usize) -> String {
+        let end = (start + self.width).min(self.memory.len());
+        let mut res = vec![];
+        let mut address = start;
+        while address < end {
+            let is_code = matches!(
+                classification.kind(address),
+                CellKind::Code | CellKind::SelfModifiedCode
+            );
+            match Instruction::decode(self.memory, address).filter(|_| is_code) {
+                Some(instruction) => {
+                    address = instruction.next();
+                    res.push(instruction.to_string());
+                }
+                None => address += 1,
+            }
+        }
+        res.join("; ")
+    }
+}
+
+impl<M: Cells + ?Sized> fmt::Display for Dump<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.memory.len();
+        let value = |address| self.memory.cell(address).unwrap();
+        let cell = (0..len)
+            .map(|address| self.format(value(address)).len())
+            .max()
+            .unwrap_or(1);
+        for start in (0..len).step_by(self.width) {
+            let values = (start..(start + self.width).min(len))
+                .map(value)
+                .collect::<Vec<_>>();
+            if self.hex {
+                write!(f, "{start:04x}:")?;
+            } else {
+                write!(f, "{start:04}:")?;
+            }
+            for &value in &values {
+                write!(f, " {:>cell$}", self.format(value))?;
+            }
+            if let Some(classification) = self.annotations {
+                let padding = (self.width - values.len()) * (cell + 1);
+                write!(f, "{:padding$} | ", "")?;
+                write!(f, "{}", self.annotation(classification, start))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A run of consecutive cells that differ between two images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub start: usize,
+    pub old: Vec<i32>,
+    pub new: Vec<i32>,
+}
+
+/// Differences between two memory images. Cells past the end of the shorter
+/// image count as zero, as they read under `Isa::Day9`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDiff {
+    pub old_len: usize,
+    pub new_len: usize,
+    pub changes: Vec<Change>,
+}
+
+impl MemoryDiff {
+    pub fn new<A: Cells + ?Sized, B: Cells + ?Sized>(old: &A, new: &B) -> Self {
+        let mut changes: Vec<Change> = vec![];
+        for address in 0..old.len().max(new.len()) {
+            let (old, new) = (
+                old.cell(address).unwrap_or(0),
+                new.cell(address).unwrap_or(0),
+            );
+            if old == new {
+                continue;
+            }
+            match changes.last_mut() {
+                Some(change) if change.start + change.old.len() == address => {
+                    change.old.push(old);
+                    change.new.push(new);
+                }
+                _ => changes.push(Change {
+                    start: address,
+                    old: vec![old],
+                    new: vec![new],
+                }),
+            }
+        }
+        Self {
+            old_len: old.len(),
+            new_len: new.len(),
+            changes,
+        }
+    }
+
+    /// True when the images have the same size and contents.
+    pub fn is_empty(&self) -> bool {
+        self.old_len == self.new_len && self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for MemoryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.old_len != self.new_len {
+            writeln!(f, "size: {} -> {}", self.old_len, self.new_len)?;
+        }
+        for change in &self.changes {
+            let end = change.start + change.old.len();
+            writeln!(
+                f,
+                "{:04}..{end:04}: {:?} -> {:?}",
+                change.start, change.old, change.new
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn load(path: &str) -> Result<Computer, String> {
+    let source = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    source
+        .trim()
+        .parse()
+        .map_err(|err| format!("{path}: {err}"))
+}
+
+/// Entry point of the `intcode` binary, returning what to print.
+///
+/// ```text
+/// intcode dump [--hex] [--annotate] [--width N] FILE
+/// intcode diff OLD NEW
+/// ```
+pub fn cli(args: &[String]) -> Result<String, String> {
+    const USAGE: &str =
+        "Usage: intcode dump [--hex] [--annotate] [--width N] FILE | intcode diff OLD NEW";
+    match args {
+        [command, options @ .., path] if command == "dump" => {
+            let computer = load(path)?;
+            let classification = computer.classify();
+            let mut dump = computer.dump();
+            let mut options = options.iter();
+            while let Some(option) = options.next() {
+                dump = match option.as_str() {
+                    "--hex" => dump.hex(),
+                    "--annotate" => dump.annotated(&classification),
+                    "--width" => {
+                        let width = options.next().and_then(|width| width.parse().ok());
+                        dump.width(width.ok_or("--width needs a number")?)
+                    }
+                    _ => return Err(format!("Unknown option {option}\n{USAGE}")),
+                };
+            }
+            Ok(dump.to_string())
+        }
+        [command, old, new] if command == "diff" => Ok(load(old)?.diff(&load(new)?).to_string()),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+impl Computer {
+    pub fn dump(&self) -> Dump<'_> {
+        Dump::new(&self.memory)
+    }
+
+    /// Changes from `self`'s memory to `other`'s.
+    pub fn diff(&self, other: &Computer) -> MemoryDiff {
+        MemoryDiff::new(&self.memory, &other.memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dump() {
+        let computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!(
+            computer.dump().width(6).to_string(),
+            "0000:  3  9  8  9 10  9\n0006:  4  9 99 -1  8\n"
+        );
+        assert_eq!(
+            Computer::from("3,255,99,-1")
+                .dump()
+                .width(2)
+                .hex()
+                .to_string(),
+            "0000:        3       ff\n0002:       63 ffffffff\n"
+        );
+
+        let classification = computer.classify();
+        assert_eq!(
+            computer.dump().annotated(&classification).to_string(),
+            "0000:  3  9  8  9 10  9  4  9 | 0000: IN [9]; 0002: EQ [9], [10], [9]; 0006: OUT [9]\n\
+             0008: 99 -1  8                | 0008: HALT\n"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut computer = Computer::from("1,0,0,0,99");
+        let before = computer.clone();
+        computer.run_program().unwrap();
+        let diff = before.diff(&computer);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.to_string(), "0000..0001: [1] -> [2]\n");
+
+        let diff = MemoryDiff::new(&[1, 2, 3, 4], &[1, 5, 6, 4, 0, 7]);
+        assert_eq!(
+            diff.to_string(),
+            "size: 4 -> 6\n0001..0003: [2, 3] -> [5, 6]\n0005..0006: [0] -> [7]\n"
+        );
+        assert!(MemoryDiff::new(&[1, 2], &[1, 2]).is_empty());
+    }
+
+    #[test]
+    fn test_cli() {
+        let dir = std::env::temp_dir();
+        let old = dir.join("intcode_cli_old.txt");
+        let new = dir.join("intcode_cli_new.txt");
+        std::fs::write(&old, "1,0,0,0,99\n").unwrap();
+        std::fs::write(&new, "2,0,0,0,99").unwrap();
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let (old_path, new_path) = (old.to_str().unwrap(), new.to_str().unwrap());
+
+        assert_eq!(
+            cli(&args(&["dump", "--hex", "--width", "3", old_path])),
+            Ok("0000:  1  0  0\n0003:  0 63\n".to_string())
+        );
+        assert_eq!(
+            cli(&args(&["diff", old_path, new_path])),
+            Ok("0000..0001: [1] -> [2]\n".to_string())
+        );
+        assert!(cli(&args(&["dump", "--width", old_path])).is_err());
+        assert!(cli(&args(&["frobnicate"])).is_err());
+
+        std::fs::remove_file(old).unwrap();
+        std::fs::remove_file(new).unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::ops::{ControlFlow, RangeInclusive};
+
+use super::builder::Halt;
+use super::device::{Device, Random};
+use super::Computer;
+
+/// The inputs to check two programs on: every sequence of `inputs` values
+/// from `range` if there are at most `max_cases` of them, otherwise
+/// `max_cases` sequences drawn at random from `seed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Domain {
+    pub inputs: usize,
+    pub range: RangeInclusive<i32>,
+    pub max_cases: usize,
+    pub seed: u64,
+    /// Runs still going after this many instructions stop with
+    /// `Halt::StepLimit`.
+    pub max_steps: usize,
+}
+
+impl Domain {
+    pub fn new(inputs: usize, range: RangeInclusive<i32>) -> Self {
+        Self {
+            inputs,
+            range,
+            max_cases: 10_000,
+            seed: 1,
+            max_steps: 10_000,
+        }
+    }
+
+    fn span(&self) -> u64 {
+        (*self.range.end() as i64 - *self.range.start() as i64 + 1).max(0) as u64
+    }
+
+    pub fn is_exhaustive(&self) -> bool {
+        matches!(
+            self.span().checked_pow(self.inputs as u32),
+            Some(count) if count <= self.max_cases as u64
+        )
+    }
+
+    /// The input sequences to check, in order.
+    pub fn cases(&self) -> Vec<Vec<i32>> {
+        let start = *self.range.start();
+        if self.is_exhaustive() {
+            let mut cases = vec![vec![]];
+            for _ in 0..self.inputs {
+                cases = cases
+                    .into_iter()
+                    .flat_map(|case: Vec<i32>| {
+                        self.range.clone().map(move |value| {
+                            let mut case = case.clone();
+                            case.push(value);
+                            case
+                        })
+                    })
+                    .collect();
+            }
+            return cases;
+        }
+
+        let mut random = Random::new(self.seed);
+        let span = self.span();
+        (0..self.max_cases)
+            .map(|_| {
+                (0..self.inputs)
+                    .map(|_| {
+                        let bits = (random.read(0) as u64) << 31 | random.read(0) as u64;
+                        (start as i64 + (bits % span) as i64) as i32
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// What a program visibly did on one input sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub output: Vec<i32>,
+    pub halt: Halt,
+}
+
+impl Outcome {
+    fn run(computer: &Computer, input: &[i32], max_steps: usize) -> Self {
+        let mut computer = computer.clone();
+        computer.inputs.extend(input);
+        let mut halt = Halt::StepLimit;
+        for _ in 0..max_steps {
+            if let ControlFlow::Break(res) = computer.run_command() {
+                halt = match res {
+                    Ok(_) => Halt::Exit,
+                    Err(err) => Halt::Error(err),
+                };
+                break;
+            }
+        }
+        Self {
+            output: computer.output,
+            halt,
+        }
+    }
+
+    /// Same output and the same way of stopping. Errors only need to be of
+    /// the same kind, since a rewritten program fails at other pointers.
+    fn same(&self, other: &Outcome) -> bool {
+        let same_halt = match (&self.halt, &other.halt) {
+            (Halt::Error(a), Halt::Error(b)) => a.name() == b.name(),
+            (a, b) => a == b,
+        };
+        same_halt && self.output == other.output
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let halt = match &self.halt {
+            Halt::Exit => "exit",
+            Halt::StepLimit => "step limit",
+            Halt::Error(err) => err.name(),
+        };
+        write!(f, "{:?} ({halt})", self.output)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counterexample {
+    pub input: Vec<i32>,
+    pub left: Outcome,
+    pub right: Outcome,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Input {:?}: left output {}, right output {}",
+            self.input, self.left, self.right
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckError {
+    /// The domain wants inputs but its range is empty, so there is nothing
+    /// to check.
+    EmptyRange,
+    Counterexample(Box<Counterexample>),
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::EmptyRange => write!(f, "Input range is empty"),
+            CheckError::Counterexample(counterexample) => write!(f, "{counterexample}"),
+        }
+    }
+}
+
+/// Runs clones of `left` and `right` on every input sequence of `domain`.
+/// Returns the number of sequences checked, or the first one they disagree
+/// on.
+pub fn check(left: &Computer, right: &Computer, domain: &Domain) -> Result<usize, CheckError> {
+    if domain.inputs > 0 && domain.range.is_empty() {
+        return Err(CheckError::EmptyRange);
+    }
+    let cases = domain.cases();
+    for input in &cases {
+        let (a, b) = (
+            Outcome::run(left, input, domain.max_steps),
+            Outcome::run(right, input, domain.max_steps),
+        );
+        if !a.same(&b) {
+            return Err(CheckError::Counterexample(Box::new(Counterexample {
+                input: input.clone(),
+                left: a,
+                right: b,
+            })));
+        }
+    }
+    Ok(cases.len())
+}
+
+impl Computer {
+    /// See `equivalence::check`.
+    pub fn equivalent(&self, other: &Computer, domain: &Domain) -> Result<usize, CheckError> {
+        check(self, other, domain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expect_counterexample(res: Result<usize, CheckError>) -> Counterexample {
+        match res {
+            Err(CheckError::Counterexample(counterexample)) => *counterexample,
+            res => panic!("Expected a counterexample, got {res:?}"),
+        }
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        // Both output whether the input is 8.
+        let left = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        let right = Computer::from("3,3,1108,-1,8,3,4,3,99");
+        let domain = Domain::new(1, 0..=20);
+        assert!(domain.is_exhaustive());
+        assert_eq!(left.equivalent(&right, &domain), Ok(21));
+
+        // Outputs whether the input is less than 8.
+        let less = Computer::from("3,9,7,9,10,9,4,9,99,-1,8");
+        let counterexample = expect_counterexample(left.equivalent(&less, &domain));
+        assert_eq!(counterexample.input, vec![0]);
+        assert_eq!(
+            counterexample.to_string(),
+            "Input [0]: left output [0] (exit), right output [1] (exit)"
+        );
+
+        // Wants a second input.
+        let twice = Computer::from("3,11,3,12,8,11,12,11,4,11,99,-1,-1");
+        let counterexample = expect_counterexample(left.equivalent(&twice, &domain));
+        assert_eq!(
+            counterexample.right,
+            Outcome {
+                output: vec![],
+                halt: Halt::Error(crate::computer::ComputerError::MissingInput { pointer: 2 })
+            }
+        );
+
+        // An empty range has no cases, which doesn't make programs equal.
+        let empty = Domain::new(1, RangeInclusive::new(5, 3));
+        assert_eq!(left.equivalent(&less, &empty), Err(CheckError::EmptyRange));
+        assert_eq!(
+            left.equivalent(&left, &Domain::new(0, RangeInclusive::new(5, 3))),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_randomized() {
+        // a + b and b + a.
+        let left = Computer::from("3,11,3,12,1,11,12,13,4,13,99,0,0,0");
+        let right = Computer::from("3,12,3,11,1,11,12,13,4,13,99,0,0,0");
+        let mut domain = Domain::new(2, -1000..=1000);
+        domain.max_cases = 50;
+        assert!(!domain.is_exhaustive());
+        let cases = domain.cases();
+        assert_eq!(cases.len(), 50);
+        assert!(cases
+            .iter()
+            .flatten()
+            .all(|value| domain.range.contains(value)));
+        assert_eq!(left.equivalent(&right, &domain), Ok(50));
+
+        // a - b and b - a.
+        let left = Computer::from("3,15,3,16,102,-1,16,16,1,15,16,17,4,17,99,0,0,0");
+        let right = Computer::from("3,16,3,15,102,-1,16,16,1,15,16,17,4,17,99,0,0,0");
+        let counterexample = expect_counterexample(left.equivalent(&right, &domain));
+        let [a, b] = counterexample.input[..] else {
+            panic!("Expected two inputs");
+        };
+        assert_eq!(counterexample.left.output, vec![a - b]);
+        assert_eq!(counterexample.right.output, vec![b - a]);
+    }
+}
@@ -0,0 +1,239 @@
+use std::error::Error;
+use std::fmt;
+
+use super::OpCode;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComputerError {
+    /// An operand resolved to an address outside of memory.
+    OutOfBounds {
+        pointer: usize,
+        address: usize,
+        size: usize,
+    },
+    /// An operand resolved to a negative address.
+    NegativeAddress {
+        pointer: usize,
+        address: i64,
+    },
+    /// The opcode is unknown or not part of the computer's ISA.
+    UnknownOpCode {
+        pointer: usize,
+        value: i32,
+    },
+    /// A parameter mode is unknown or not part of the computer's ISA.
+    InvalidMode {
+        pointer: usize,
+        value: i32,
+    },
+    MissingInput {
+        pointer: usize,
+    },
+    MaxIterations(u32),
+    /// The instruction at `pointer` costs more gas than is left.
+    OutOfGas {
+        pointer: usize,
+        cost: u64,
+        remaining: u64,
+    },
+    /// The loop detector saw the same state twice. `length` is the number of
+    /// steps in one cycle and `addresses` the instructions run in it.
+    InfiniteLoop {
+        pointer: usize,
+        length: u64,
+        addresses: Vec<usize>,
+    },
+    /// `Add`, `Mul` or `AdjustBase` overflowed under `Arithmetic::Checked`.
+    Overflow {
+        pointer: usize,
+        op: OpCode,
+        a: i32,
+        b: i32,
+    },
+    /// A destination parameter used immediate mode under
+    /// `Validation::Strict`. `param` is the parameter's offset from the
+    /// pointer.
+    ImmediateWrite {
+        pointer: usize,
+        op: OpCode,
+        param: usize,
+    },
+    /// An operand resolved to an address above `Sandbox::max_address`.
+    AddressLimit {
+        pointer: usize,
+        address: usize,
+        max: usize,
+    },
+    /// Growing memory to `cells` cells would exceed `Sandbox::max_cells`.
+    MemoryLimit {
+        pointer: usize,
+        cells: usize,
+        max: usize,
+    },
+    /// `register_op` was given a built-in opcode, one above 99, or more
+    /// parameters than an instruction has mode digits for.
+    InvalidCustomOp {
+        code: u32,
+        arity: usize,
+    },
+    NotEnoughMemory {
+        size: usize,
+    },
+    /// `map_device` was given a range that runs past `usize::MAX`.
+    DeviceOverflow {
+        start: usize,
+        len: usize,
+    },
+    /// `map_device` was given more addresses than the device has.
+    DeviceTooSmall {
+        len: usize,
+        size: usize,
+    },
+    /// `map_device` was given a range overlapping an earlier mapping.
+    DeviceOverlap {
+        start: usize,
+        end: usize,
+        other_start: usize,
+        other_end: usize,
+    },
+    /// A `Scheduler` was given the index of a machine it doesn't have.
+    UnknownMachine {
+        id: usize,
+    },
+    /// A program cell isn't an integer that fits in an `i32`.
+    InvalidCell {
+        address: usize,
+        value: String,
+    },
+    /// The program ran but at least one diagnostic output was non-zero.
+    DiagnosticsFailed(Vec<i32>),
+}
+
+impl ComputerError {
+    /// Name of the variant, e.g. `"MissingInput"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComputerError::OutOfBounds { .. } => "OutOfBounds",
+            ComputerError::NegativeAddress { .. } => "NegativeAddress",
+            ComputerError::UnknownOpCode { .. } => "UnknownOpCode",
+            ComputerError::InvalidMode { .. } => "InvalidMode",
+            ComputerError::MissingInput { .. } => "MissingInput",
+            ComputerError::MaxIterations(_) => "MaxIterations",
+            ComputerError::OutOfGas { .. } => "OutOfGas",
+            ComputerError::InfiniteLoop { .. } => "InfiniteLoop",
+            ComputerError::Overflow { .. } => "Overflow",
+            ComputerError::ImmediateWrite { .. } => "ImmediateWrite",
+            ComputerError::AddressLimit { .. } => "AddressLimit",
+            ComputerError::MemoryLimit { .. } => "MemoryLimit",
+            ComputerError::InvalidCustomOp { .. } => "InvalidCustomOp",
+            ComputerError::NotEnoughMemory { .. } => "NotEnoughMemory",
+            ComputerError::DeviceOverflow { .. } => "DeviceOverflow",
+            ComputerError::DeviceTooSmall { .. } => "DeviceTooSmall",
+            ComputerError::DeviceOverlap { .. } => "DeviceOverlap",
+            ComputerError::UnknownMachine { .. } => "UnknownMachine",
+            ComputerError::InvalidCell { .. } => "InvalidCell",
+            ComputerError::DiagnosticsFailed(_) => "DiagnosticsFailed",
+        }
+    }
+}
+
+impl fmt::Display for ComputerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ComputerError::OutOfBounds {
+                pointer,
+                address,
+                size,
+            } => write!(
+                f,
+                "Address {address} not in memory of size {size} at pointer {pointer}"
+            ),
+            ComputerError::NegativeAddress { pointer, address } => {
+                write!(f, "Negative address {address} at pointer {pointer}")
+            }
+            ComputerError::UnknownOpCode { pointer, value } => {
+                write!(f, "Unknown opcode in {value} at pointer {pointer}")
+            }
+            ComputerError::InvalidMode { pointer, value } => {
+                write!(f, "Invalid parameter mode in {value} at pointer {pointer}")
+            }
+            ComputerError::MissingInput { pointer } => {
+                write!(f, "Input is None at pointer {pointer}")
+            }
+            ComputerError::MaxIterations(max) => write!(f, "Hit max iterations {max}"),
+            ComputerError::OutOfGas {
+                pointer,
+                cost,
+                remaining,
+            } => write!(
+                f,
+                "Out of gas at pointer {pointer}: instruction costs {cost}, {remaining} left"
+            ),
+            ComputerError::InfiniteLoop {
+                pointer,
+                length,
+                addresses,
+            } => write!(
+                f,
+                "Infinite loop of {length} steps at pointer {pointer} through {addresses:?}"
+            ),
+            ComputerError::Overflow { pointer, op, a, b } => {
+                write!(f, "{op:?} overflowed with {a} and {b} at pointer {pointer}")
+            }
+            ComputerError::ImmediateWrite { pointer, op, param } => write!(
+                f,
+                "{op:?} at pointer {pointer} writes through immediate-mode parameter {param}"
+            ),
+            ComputerError::AddressLimit {
+                pointer,
+                address,
+                max,
+            } => write!(
+                f,
+                "Address {address} above sandbox limit {max} at pointer {pointer}"
+            ),
+            ComputerError::MemoryLimit {
+                pointer,
+                cells,
+                max,
+            } => write!(
+                f,
+                "Growing memory to {cells} cells exceeds sandbox limit {max} at pointer {pointer}"
+            ),
+            ComputerError::InvalidCustomOp { code, arity } => write!(
+                f,
+                "Opcode {code} with {arity} parameters is built in or doesn't fit in an instruction"
+            ),
+            ComputerError::NotEnoughMemory { size } => {
+                write!(f, "Not enough memory to set parameters, size={size}")
+            }
+            ComputerError::DeviceOverflow { start, len } => {
+                write!(f, "Range of {len} addresses from {start} overflows")
+            }
+            ComputerError::DeviceTooSmall { len, size } => {
+                write!(
+                    f,
+                    "Range of {len} addresses is larger than the device size {size}"
+                )
+            }
+            ComputerError::DeviceOverlap {
+                start,
+                end,
+                other_start,
+                other_end,
+            } => write!(
+                f,
+                "Range {start}..{end} overlaps device at {other_start}..{other_end}"
+            ),
+            ComputerError::UnknownMachine { id } => write!(f, "No machine {id}"),
+            ComputerError::InvalidCell { address, value } => {
+                write!(f, "Cell {address} is not an i32: {value}")
+            }
+            ComputerError::DiagnosticsFailed(diagnostics) => {
+                write!(f, "Diagnostics failed! Output: {diagnostics:?}")
+            }
+        }
+    }
+}
+
+impl Error for ComputerError {}
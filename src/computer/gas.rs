@@ -0,0 +1,177 @@
+use super::{Computer, ComputerError, OpCode};
+
+/// Gas charged for each instruction, before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CostTable {
+    pub add: u64,
+    pub mul: u64,
+    pub input: u64,
+    pub output: u64,
+    pub jump_true: u64,
+    pub jump_false: u64,
+    pub less_than: u64,
+    pub equal: u64,
+    pub adjust_base: u64,
+    pub exit: u64,
+    /// Every instruction added with `register_op`.
+    pub custom: u64,
+}
+
+impl CostTable {
+    /// Every instruction, including `Exit`, costs `cost`.
+    pub fn uniform(cost: u64) -> Self {
+        Self {
+            add: cost,
+            mul: cost,
+            input: cost,
+            output: cost,
+            jump_true: cost,
+            jump_false: cost,
+            less_than: cost,
+            equal: cost,
+            adjust_base: cost,
+            exit: cost,
+            custom: cost,
+        }
+    }
+
+    /// The cost of `op`, or of a custom instruction for `None`.
+    pub fn cost(&self, op: Option<&OpCode>) -> u64 {
+        match op {
+            Some(OpCode::Add) => self.add,
+            Some(OpCode::Mul) => self.mul,
+            Some(OpCode::Input) => self.input,
+            Some(OpCode::Output) => self.output,
+            Some(OpCode::JumpTrue) => self.jump_true,
+            Some(OpCode::JumpFalse) => self.jump_false,
+            Some(OpCode::LessThan) => self.less_than,
+            Some(OpCode::Equal) => self.equal,
+            Some(OpCode::AdjustBase) => self.adjust_base,
+            Some(OpCode::Exit) => self.exit,
+            None => self.custom,
+        }
+    }
+}
+
+/// Multiplication and I/O cost more than the rest; halting is free.
+impl Default for CostTable {
+    fn default() -> Self {
+        Self {
+            mul: 3,
+            input: 5,
+            output: 5,
+            exit: 0,
+            ..Self::uniform(1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gas {
+    pub costs: CostTable,
+    pub budget: u64,
+    pub used: u64,
+}
+
+impl Gas {
+    pub fn remaining(&self) -> u64 {
+        self.budget - self.used
+    }
+}
+
+impl Computer {
+    /// Limits the run to `budget` gas, charged per instruction by `costs`.
+    /// An instruction that costs more than what is left stops the run with
+    /// `ComputerError::OutOfGas` without running. The budget replaces the
+    /// instruction limit of `run_program`.
+    pub fn meter(&mut self, budget: u64, costs: CostTable) {
+        self.gas = Some(Gas {
+            costs,
+            budget,
+            used: 0,
+        });
+    }
+
+    /// Gas left, if metering is on.
+    pub fn remaining_gas(&self) -> Option<u64> {
+        self.gas.as_ref().map(Gas::remaining)
+    }
+
+    /// Called before every instruction, with `None` for custom instructions.
+    pub(super) fn charge(&mut self, op: Option<&OpCode>) -> Result<(), ComputerError> {
+        let pointer = self.pointer;
+        let Some(gas) = self.gas.as_mut() else {
+            return Ok(());
+        };
+        let cost = gas.costs.cost(op);
+        if cost > gas.remaining() {
+            return Err(ComputerError::OutOfGas {
+                pointer,
+                cost,
+                remaining: gas.remaining(),
+            });
+        }
+        gas.used += cost;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::builder::Halt;
+
+    #[test]
+    fn test_meter() {
+        // Add, Mul, Output, Exit.
+        let program = "1,0,0,0,2,0,0,0,4,0,99";
+        let mut computer = Computer::from(program);
+        computer.meter(100, CostTable::default());
+        computer.run_program().unwrap();
+        assert_eq!(computer.remaining_gas(), Some(100 - 1 - 3 - 5));
+
+        let mut computer = Computer::from(program);
+        computer.meter(8, CostTable::default());
+        let err = computer.run_program().unwrap_err();
+        assert_eq!(
+            err,
+            ComputerError::OutOfGas {
+                pointer: 8,
+                cost: 5,
+                remaining: 4
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Out of gas at pointer 8: instruction costs 5, 4 left"
+        );
+        assert_eq!(computer.output, vec![]);
+        assert_eq!(computer.remaining_gas(), Some(4));
+
+        // Counts cell 20 down from 600: 1201 instructions.
+        let program = "1001,20,-1,20,1005,20,0,99,0,0,0,0,0,0,0,0,0,0,0,0,600";
+        assert_eq!(
+            Computer::from(program).run_program(),
+            Err(ComputerError::MaxIterations(1000))
+        );
+        let mut computer = Computer::from(program);
+        computer.meter(2000, CostTable::uniform(1));
+        assert_eq!(computer.run_program(), Ok(1001));
+        assert_eq!(computer.remaining_gas(), Some(2000 - 1201));
+    }
+
+    #[test]
+    fn test_builder_gas() {
+        let result = Computer::builder()
+            .program("1105,1,0")
+            .gas(10, CostTable::uniform(2))
+            .run()
+            .unwrap();
+        assert_eq!(result.steps, 6);
+        assert!(matches!(
+            result.halt,
+            Halt::Error(ComputerError::OutOfGas { remaining: 0, .. })
+        ));
+        assert_eq!(Computer::from("99").remaining_gas(), None);
+    }
+}
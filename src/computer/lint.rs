@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use super::cfg::{ControlFlowGraph, Edge, Target};
+use super::classify::{CellKind, Classification};
+use super::disasm::{DecodeError, Instruction};
+use super::memory::Cells;
+use super::{Computer, Isa, ParameterMode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintKind {
+    /// Unknown opcode, or one outside the instruction set.
+    UnknownOpCode,
+    /// Unknown parameter mode, or one outside the instruction set.
+    InvalidMode,
+    /// A destination parameter in immediate mode.
+    ImmediateWrite,
+    /// A static jump target past the end of memory.
+    JumpOutOfMemory,
+    /// Execution can reach the end of memory, or an instruction whose
+    /// parameters run past it.
+    FallsOffEnd,
+    /// Cells that decode as instructions but can't be reached.
+    Unreachable,
+}
+
+impl LintKind {
+    /// Stable identifier used in the machine-readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LintKind::UnknownOpCode => "unknown-opcode",
+            LintKind::InvalidMode => "invalid-mode",
+            LintKind::ImmediateWrite => "immediate-write",
+            LintKind::JumpOutOfMemory => "jump-out-of-memory",
+            LintKind::FallsOffEnd => "falls-off-end",
+            LintKind::Unreachable => "unreachable",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub address: usize,
+    pub kind: LintKind,
+    pub message: String,
+}
+
+impl Warning {
+    fn new(address: usize, kind: LintKind, message: String) -> Self {
+        Self {
+            address,
+            kind,
+            message,
+        }
+    }
+
+    /// One JSON object, e.g.
+    /// `{"address":4,"kind":"unknown-opcode","message":"unknown opcode in 42"}`.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"address\":{},\"kind\":\"{}\",\"message\":{}}}",
+            self.address,
+            self.kind.code(),
+            json_string(&self.message)
+        )
+    }
+}
+
+/// `text` as a quoted JSON string.
+fn json_string(text: &str) -> String {
+    let mut res = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}: {}: {}",
+            self.address,
+            self.kind.code(),
+            self.message
+        )
+    }
+}
+
+/// Statically checks every instruction reachable from address 0 against
+/// `isa`. Warnings are sorted by address.
+///
+/// Unreachable code is only reported when the program has no indirect jumps,
+/// since those could land anywhere.
+pub fn lint<M: Cells + ?Sized>(memory: &M, isa: Isa) -> Vec<Warning> {
+    let cfg = ControlFlowGraph::new(memory, 0);
+    let mut warnings = vec![];
+    let mut jump_targets = BTreeSet::new();
+
+    for block in cfg.blocks.values() {
+        for instruction in &block.instructions {
+            check_instruction(instruction, isa, &mut warnings);
+        }
+        for edge in &block.successors {
+            if let Edge::Jump(Target::Static(target)) = edge {
+                jump_targets.insert(*target);
+                let instruction = block.instructions.last().unwrap();
+                if *target >= memory.len() {
+                    warnings.push(Warning::new(
+                        instruction.address,
+                        LintKind::JumpOutOfMemory,
+                        format!(
+                            "jump target {} outside memory of size {}",
+                            instruction.params[1],
+                            memory.len()
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    for block in cfg.blocks.values() {
+        if block.halts() || !block.successors.is_empty() {
+            continue;
+        }
+        let address = block.end();
+        let warning = match Instruction::try_decode(memory, address) {
+            Err(DecodeError::OutOfMemory) if jump_targets.contains(&address) => continue,
+            Err(DecodeError::OutOfMemory) => Warning::new(
+                address,
+                LintKind::FallsOffEnd,
+                "execution runs past the end of memory".to_string(),
+            ),
+            Err(DecodeError::Truncated) => Warning::new(
+                address,
+                LintKind::FallsOffEnd,
+                format!(
+                    "instruction {} runs past the end of memory",
+                    memory.cell(address).unwrap()
+                ),
+            ),
+            Err(DecodeError::UnknownOpCode(value)) => Warning::new(
+                address,
+                LintKind::UnknownOpCode,
+                format!("unknown opcode in {value}"),
+            ),
+            Err(DecodeError::InvalidMode(value)) => Warning::new(
+                address,
+                LintKind::InvalidMode,
+                format!("invalid parameter mode in {value}"),
+            ),
+            Ok(_) => continue,
+        };
+        warnings.push(warning);
+    }
+
+    if cfg.indirect_jumps().is_empty() {
+        warnings.extend(unreachable(memory));
+    }
+
+    warnings.sort_by_key(|warning| (warning.address, warning.kind));
+    warnings
+}
+
+fn check_instruction(instruction: &Instruction, isa: Isa, warnings: &mut Vec<Warning>) {
+    let address = instruction.address;
+    let op = &instruction.op;
+    if !isa.supports(op) {
+        warnings.push(Warning::new(
+            address,
+            LintKind::UnknownOpCode,
+            format!("{op:?} is not part of the {isa:?} instruction set"),
+        ));
+    }
+    for mode in &instruction.modes[..op.arity()] {
+        if !isa.supports_mode(mode) {
+            warnings.push(Warning::new(
+                address,
+                LintKind::InvalidMode,
+                format!("{mode:?} mode is not part of the {isa:?} instruction set"),
+            ));
+            break;
+        }
+    }
+    if let Some(param) = instruction.destination() {
+        if instruction.modes[param] == ParameterMode::Immediate {
+            warnings.push(Warning::new(
+                address,
+                LintKind::ImmediateWrite,
+                format!(
+                    "{op:?} writes through immediate-mode parameter {}",
+                    param + 1
+                ),
+            ));
+        }
+    }
+}
+
+/// Runs of unclassified cells that decode exactly as a sequence of
+/// instructions.
+fn unreachable<M: Cells + ?Sized>(memory: &M) -> Vec<Warning> {
+    let classification = Classification::new(memory);
+    let mut warnings = vec![];
+    let mut address = 0;
+
+    while address < memory.len() {
+        if classification.kind(address) != CellKind::Unknown {
+            address += 1;
+            continue;
+        }
+        let start = address;
+        while classification.kind(address) == CellKind::Unknown && address < memory.len() {
+            address += 1;
+        }
+
+        let mut next = start;
+        while let Some(instruction) = Instruction::decode(memory, next) {
+            if instruction.next() > address {
+                break;
+            }
+            next = instruction.next();
+        }
+        if next == address {
+            warnings.push(Warning::new(
+                start,
+                LintKind::Unreachable,
+                format!("cells {start}..{address} decode as instructions but are unreachable"),
+            ));
+        }
+    }
+    warnings
+}
+
+impl Computer {
+    pub fn lint(&self) -> Vec<Warning> {
+        lint(&self.memory, self.isa)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean() {
+        let computer = Computer::from("3,9,8,9,10,9,4,9,99,-1,8");
+        assert_eq!(computer.lint(), vec![]);
+
+        let mut computer =
+            Computer::from("109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99");
+        computer.isa = Isa::Day9;
+        assert_eq!(computer.lint(), vec![]);
+    }
+
+    #[test]
+    fn test_lint() {
+        let computer = Computer::from("11101,1,2,3,1105,1,100,1,0,0,0,99");
+        let warnings = computer
+            .lint()
+            .iter()
+            .map(|warning| warning.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                "0000: immediate-write: Add writes through immediate-mode parameter 3",
+                "0004: jump-out-of-memory: jump target 100 outside memory of size 12",
+                "0007: unreachable: cells 7..12 decode as instructions but are unreachable",
+            ]
+        );
+
+        let mut computer = Computer::from("1101,0,0,0,1,0,0,0,42");
+        computer.isa = Isa::Day2;
+        let warnings = computer.lint();
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| warning.kind)
+                .collect::<Vec<_>>(),
+            vec![LintKind::InvalidMode, LintKind::UnknownOpCode]
+        );
+        assert_eq!(
+            warnings[1].to_json(),
+            r#"{"address":8,"kind":"unknown-opcode","message":"unknown opcode in 42"}"#
+        );
+        assert_eq!(
+            json_string("say \"hi\"\\\n\u{1}é"),
+            r#""say \"hi\"\\\n\u0001é""#
+        );
+
+        let computer = Computer::from("1,0,0,0,1,0");
+        assert_eq!(
+            computer.lint()[0].to_string(),
+            "0004: falls-off-end: instruction 1 runs past the end of memory"
+        );
+    }
+}
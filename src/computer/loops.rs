@@ -0,0 +1,131 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::ControlFlow;
+
+use super::{Computer, ComputerError};
+
+/// Remembers the machine state at every backward jump. Seeing the same state
+/// twice means the program will repeat forever, since execution is
+/// deterministic. Custom opcodes with side effects outside the computer and
+/// memory-mapped devices break that assumption.
+#[derive(Debug, Clone, Default)]
+pub struct LoopDetector {
+    /// Number of instructions run since the detector was enabled.
+    steps: u64,
+    /// State hash to the step it was first seen at.
+    seen: HashMap<u64, u64>,
+}
+
+impl Computer {
+    /// Turns on loop detection for `run_program`, which then stops with
+    /// `ComputerError::InfiniteLoop` instead of running into its step cap.
+    pub fn detect_loops(&mut self) {
+        self.loop_detector = Some(LoopDetector::default());
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.pointer.hash(&mut hasher);
+        self.memory.hash(&mut hasher);
+        self.input.hash(&mut hasher);
+        self.inputs.hash(&mut hasher);
+        self.relative_base.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn same_state(&self, other: &Computer) -> bool {
+        self.pointer == other.pointer
+            && self.memory == other.memory
+            && self.input == other.input
+            && self.inputs == other.inputs
+            && self.relative_base == other.relative_base
+    }
+
+    /// Called after every instruction with the pointer it ran at.
+    pub(super) fn check_loop(&mut self, from: usize) -> Result<(), ComputerError> {
+        let backward = self.pointer <= from;
+        let hash = backward.then(|| self.state_hash());
+        let Some(detector) = self.loop_detector.as_mut() else {
+            return Ok(());
+        };
+        detector.steps += 1;
+        let Some(hash) = hash else {
+            return Ok(());
+        };
+        let steps = detector.steps;
+        let Some(&first) = detector.seen.get(&hash) else {
+            detector.seen.insert(hash, steps);
+            return Ok(());
+        };
+
+        // Replay one cycle on a copy, which both rules out a hash collision
+        // and collects the addresses in the loop.
+        let length = steps - first;
+        let mut replay = self.clone();
+        replay.loop_detector = None;
+        let mut addresses = vec![];
+        for _ in 0..length {
+            addresses.push(replay.pointer);
+            if let ControlFlow::Break(_) = replay.run_command() {
+                return Ok(());
+            }
+        }
+        if !replay.same_state(self) {
+            return Ok(());
+        }
+        addresses.sort_unstable();
+        addresses.dedup();
+        Err(ComputerError::InfiniteLoop {
+            pointer: self.pointer,
+            length,
+            addresses,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_loop() {
+        let mut computer = Computer::from("1105,1,0");
+        computer.detect_loops();
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::InfiniteLoop {
+                pointer: 0,
+                length: 1,
+                addresses: vec![0]
+            })
+        );
+
+        // Adding zero leaves the state unchanged.
+        let mut computer = Computer::from("1001,9,0,9,1105,1,0,99,0,5");
+        computer.detect_loops();
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::InfiniteLoop {
+                pointer: 0,
+                length: 2,
+                addresses: vec![0, 4]
+            })
+        );
+    }
+
+    #[test]
+    fn test_long_running() {
+        // The counter keeps changing, so no state repeats.
+        let mut computer = Computer::from("1001,7,1,7,1105,1,0,0");
+        computer.detect_loops();
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::MaxIterations(1000))
+        );
+
+        let mut computer = Computer::from("1001,12,-1,12,1005,12,0,1101,0,0,0,99,3");
+        computer.detect_loops();
+        assert_eq!(computer.run_program(), Ok(0));
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+/// Number of cells per page.
+pub const PAGE_SIZE: usize = 256;
+
+type Page = Arc<[i32; PAGE_SIZE]>;
+
+/// Copy-on-write paged memory. Cloning shares every page, so forking a
+/// `Computer` is O(1). A write copies the page table and the written page if
+/// they are still shared with another clone.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Memory {
+    pages: Arc<Vec<Page>>,
+    len: usize,
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, address: usize) -> Option<&i32> {
+        if address < self.len {
+            Some(&self.pages[address / PAGE_SIZE][address % PAGE_SIZE])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, address: usize) -> Option<&mut i32> {
+        if address >= self.len {
+            return None;
+        }
+        let page = Arc::make_mut(&mut self.pages)
+            .get_mut(address / PAGE_SIZE)
+            .unwrap();
+        Some(&mut Arc::make_mut(page)[address % PAGE_SIZE])
+    }
+
+    /// Grows memory to `len` cells, filling new cells with `value`. Memory
+    /// never shrinks.
+    pub fn resize(&mut self, len: usize, value: i32) {
+        let old = self.len;
+        if len <= old {
+            return;
+        }
+        let pages = Arc::make_mut(&mut self.pages);
+        while pages.len() * PAGE_SIZE < len {
+            pages.push(Arc::new([0; PAGE_SIZE]));
+        }
+        self.len = len;
+        if value != 0 {
+            for address in old..len {
+                self[address] = value;
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &i32> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.iter())
+            .take(self.len)
+    }
+
+    /// True when the cells from `address` on hold exactly `cells`.
+    pub fn matches(&self, address: usize, cells: &[i32]) -> bool {
+        cells
+            .iter()
+            .enumerate()
+            .all(|(offset, value)| self.get(address + offset) == Some(value))
+    }
+
+    pub fn to_vec(&self) -> Vec<i32> {
+        self.iter().copied().collect()
+    }
+
+    /// Number of pages `self` and `other` still share.
+    pub fn shared_pages(&self, other: &Memory) -> usize {
+        self.pages
+            .iter()
+            .zip(other.pages.iter())
+            .filter(|(a, b)| Arc::ptr_eq(a, b))
+            .count()
+    }
+}
+
+impl Index<usize> for Memory {
+    type Output = i32;
+
+    fn index(&self, address: usize) -> &i32 {
+        let len = self.len;
+        self.get(address)
+            .unwrap_or_else(|| panic!("Address {address} not in memory of size {len}"))
+    }
+}
+
+impl IndexMut<usize> for Memory {
+    fn index_mut(&mut self, address: usize) -> &mut i32 {
+        let len = self.len;
+        self.get_mut(address)
+            .unwrap_or_else(|| panic!("Address {address} not in memory of size {len}"))
+    }
+}
+
+impl From<Vec<i32>> for Memory {
+    fn from(cells: Vec<i32>) -> Self {
+        cells.into_iter().collect()
+    }
+}
+
+impl FromIterator<i32> for Memory {
+    fn from_iter<I: IntoIterator<Item = i32>>(iter: I) -> Self {
+        let mut pages = vec![];
+        let mut len = 0;
+        for value in iter {
+            if len % PAGE_SIZE == 0 {
+                pages.push([0; PAGE_SIZE]);
+            }
+            pages.last_mut().unwrap()[len % PAGE_SIZE] = value;
+            len += 1;
+        }
+        Self {
+            pages: Arc::new(pages.into_iter().map(Arc::new).collect()),
+            len,
+        }
+    }
+}
+
+impl PartialEq<Vec<i32>> for Memory {
+    fn eq(&self, other: &Vec<i32>) -> bool {
+        self.len == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl PartialEq<Memory> for Vec<i32> {
+    fn eq(&self, other: &Memory) -> bool {
+        other == self
+    }
+}
+
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Read access to a memory image, for tools that work on both `Memory` and
+/// plain slices.
+pub trait Cells {
+    fn cell(&self, address: usize) -> Option<i32>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Cells for Memory {
+    fn cell(&self, address: usize) -> Option<i32> {
+        self.get(address).copied()
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T: AsRef<[i32]> + ?Sized> Cells for T {
+    fn cell(&self, address: usize) -> Option<i32> {
+        self.as_ref().get(address).copied()
+    }
+
+    fn len(&self) -> usize {
+        self.as_ref().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut memory = Memory::from((0..1000).collect::<Vec<_>>());
+        let fork = memory.clone();
+        assert_eq!(memory.shared_pages(&fork), 4);
+
+        memory[300] = -1;
+        assert_eq!(memory.shared_pages(&fork), 3);
+        assert_eq!(memory[300], -1);
+        assert_eq!(fork[300], 300);
+        assert_ne!(memory, fork);
+
+        memory.resize(1100, 0);
+        assert_eq!(memory.len(), 1100);
+        assert_eq!(memory.get(1099), Some(&0));
+        assert_eq!(memory.get(1100), None);
+        assert_eq!(fork.len(), 1000);
+    }
+
+    #[test]
+    fn test_conversions() {
+        let memory = Memory::from(vec![1, 2, 3]);
+        assert_eq!(memory, vec![1, 2, 3]);
+        assert_eq!(memory.to_vec(), vec![1, 2, 3]);
+        assert_eq!(format!("{memory:?}"), "[1, 2, 3]");
+        assert_eq!(memory.cell(2), Some(3));
+        assert_eq!([1, 2].cell(2), None);
+    }
+}
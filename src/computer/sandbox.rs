@@ -0,0 +1,106 @@
+use super::{Computer, ComputerError};
+
+/// Limits for running untrusted programs under `Isa::Day9`, where writing to
+/// a huge address would otherwise grow memory to match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Sandbox {
+    /// Most cells memory may grow to. Memory the program starts with is not
+    /// checked.
+    pub max_cells: Option<usize>,
+    /// Highest address operands may resolve to, including the addresses of
+    /// the parameters themselves.
+    pub max_address: Option<usize>,
+}
+
+impl Sandbox {
+    pub fn new(max_cells: usize, max_address: usize) -> Self {
+        Self {
+            max_cells: Some(max_cells),
+            max_address: Some(max_address),
+        }
+    }
+
+    pub(super) fn check_address(
+        &self,
+        pointer: usize,
+        address: usize,
+    ) -> Result<(), ComputerError> {
+        match self.max_address {
+            Some(max) if address > max => Err(ComputerError::AddressLimit {
+                pointer,
+                address,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) fn check_cells(&self, pointer: usize, cells: usize) -> Result<(), ComputerError> {
+        match self.max_cells {
+            Some(max) if cells > max => Err(ComputerError::MemoryLimit {
+                pointer,
+                cells,
+                max,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Computer {
+    /// Limits memory growth, see `Sandbox`.
+    pub fn sandbox(&mut self, sandbox: Sandbox) {
+        self.sandbox = sandbox;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Isa;
+
+    #[test]
+    fn test_address_limit() {
+        // Writes to address 1 << 30.
+        let mut computer = Computer::from("1101,1,1,1073741824,99");
+        computer.isa = Isa::Day9;
+        computer.sandbox(Sandbox {
+            max_address: Some(1000),
+            ..Sandbox::default()
+        });
+        let err = computer.run_program().unwrap_err();
+        assert_eq!(
+            err,
+            ComputerError::AddressLimit {
+                pointer: 0,
+                address: 1 << 30,
+                max: 1000
+            }
+        );
+        assert_eq!(err.name(), "AddressLimit");
+        assert_eq!(computer.len(), 5);
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let program = "1101,1,1,20,1101,1,1,40,99";
+        let mut computer = Computer::from(program);
+        computer.isa = Isa::Day9;
+        computer.sandbox(Sandbox::new(32, 100));
+        assert_eq!(
+            computer.run_program(),
+            Err(ComputerError::MemoryLimit {
+                pointer: 4,
+                cells: 41,
+                max: 32
+            })
+        );
+        assert_eq!(computer.len(), 21);
+        assert_eq!(computer.memory[20], 2);
+
+        let mut computer = Computer::from(program);
+        computer.isa = Isa::Day9;
+        computer.sandbox(Sandbox::new(41, 40));
+        assert!(computer.run_program().is_ok());
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt;
+use std::ops::ControlFlow;
+
+use super::{Computer, ComputerError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MachineState {
+    Ready,
+    /// Waiting on `Input` with no queued input and no fallback.
+    Blocked,
+    Halted,
+    Failed(ComputerError),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Instructions run.
+    pub steps: usize,
+    /// Times the machine was given a quantum.
+    pub slices: usize,
+    /// Times the machine blocked on input.
+    pub waits: usize,
+    /// Values delivered to the machine by the scheduler.
+    pub received: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    pub computer: Computer,
+    pub state: MachineState,
+    pub stats: Stats,
+    /// Machines that receive this machine's outputs.
+    pub targets: Vec<usize>,
+}
+
+impl Machine {
+    fn is_waiting(&self) -> bool {
+        let computer = &self.computer;
+        computer.at_input() && computer.inputs.is_empty() && computer.input.is_none()
+    }
+}
+
+/// How `Scheduler::run` ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Every machine halted or failed.
+    Finished,
+    /// The machines left are all blocked with no input to deliver.
+    Deadlock(Vec<usize>),
+    RoundLimit,
+}
+
+/// Runs machines round-robin, `quantum` instructions at a time. A machine's
+/// outputs are queued as input to the machines it is connected to.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    pub machines: Vec<Machine>,
+    quantum: usize,
+}
+
+impl Scheduler {
+    pub fn new(quantum: usize) -> Self {
+        Self {
+            machines: vec![],
+            quantum: quantum.max(1),
+        }
+    }
+
+    /// Adds a machine and returns its index.
+    pub fn add(&mut self, computer: Computer) -> usize {
+        self.machines.push(Machine {
+            computer,
+            state: MachineState::Ready,
+            stats: Stats::default(),
+            targets: vec![],
+        });
+        self.machines.len() - 1
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), ComputerError> {
+        if index < self.machines.len() {
+            Ok(())
+        } else {
+            Err(ComputerError::UnknownMachine { id: index })
+        }
+    }
+
+    /// Sends every output of `from` to `to`.
+    pub fn connect(&mut self, from: usize, to: usize) -> Result<(), ComputerError> {
+        self.check_index(from)?;
+        self.check_index(to)?;
+        self.machines[from].targets.push(to);
+        Ok(())
+    }
+
+    /// Queues `value` as input for machine `to`, unblocking it.
+    pub fn send(&mut self, to: usize, value: i32) -> Result<(), ComputerError> {
+        self.check_index(to)?;
+        self.deliver(to, value);
+        Ok(())
+    }
+
+    fn deliver(&mut self, to: usize, value: i32) {
+        let machine = &mut self.machines[to];
+        machine.computer.inputs.push_back(value);
+        machine.stats.received += 1;
+        if machine.state == MachineState::Blocked {
+            machine.state = MachineState::Ready;
+        }
+    }
+
+    /// Runs one quantum of machine `index`.
+    fn run_slice(&mut self, index: usize) {
+        let mut sent = vec![];
+        let machine = &mut self.machines[index];
+        machine.stats.slices += 1;
+
+        for _ in 0..self.quantum {
+            if machine.is_waiting() {
+                machine.state = MachineState::Blocked;
+                machine.stats.waits += 1;
+                break;
+            }
+            let outputs = machine.computer.output.len();
+            let res = machine.computer.run_command();
+            machine.stats.steps += 1;
+            sent.extend_from_slice(&machine.computer.output[outputs..]);
+            match res {
+                ControlFlow::Continue(_) => {}
+                ControlFlow::Break(Ok(_)) => {
+                    machine.state = MachineState::Halted;
+                    break;
+                }
+                ControlFlow::Break(Err(err)) => {
+                    machine.state = MachineState::Failed(err);
+                    break;
+                }
+            }
+        }
+
+        let targets = machine.targets.clone();
+        for value in sent {
+            for &to in &targets {
+                self.deliver(to, value);
+            }
+        }
+    }
+
+    /// Runs machines until all have stopped, they deadlock, or `max_rounds`
+    /// rounds have passed.
+    pub fn run(&mut self, max_rounds: usize) -> Status {
+        for _ in 0..max_rounds {
+            let mut progress = false;
+            for index in 0..self.machines.len() {
+                if self.machines[index].state == MachineState::Ready {
+                    let steps = self.machines[index].stats.steps;
+                    self.run_slice(index);
+                    progress |= self.machines[index].stats.steps > steps;
+                }
+            }
+
+            let blocked: Vec<_> = (0..self.machines.len())
+                .filter(|&index| self.machines[index].state == MachineState::Blocked)
+                .collect();
+            let runnable = self
+                .machines
+                .iter()
+                .any(|machine| machine.state == MachineState::Ready);
+            if !runnable && blocked.is_empty() {
+                return Status::Finished;
+            }
+            if !runnable && !progress {
+                return Status::Deadlock(blocked);
+            }
+        }
+        Status::RoundLimit
+    }
+}
+
+impl fmt::Display for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, machine) in self.machines.iter().enumerate() {
+            let state = match &machine.state {
+                MachineState::Failed(err) => format!("failed ({err})"),
+                state => format!("{state:?}").to_lowercase(),
+            };
+            let stats = &machine.stats;
+            writeln!(
+                f,
+                "machine {index}: {state}, {} steps, {} slices, {} waits, {} received",
+                stats.steps, stats.slices, stats.waits, stats.received
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline() {
+        let mut scheduler = Scheduler::new(2);
+        // Adds one, then doubles.
+        let add = scheduler.add(Computer::from("3,9,1001,9,1,9,4,9,99,0"));
+        let double = scheduler.add(Computer::from("3,9,102,2,9,9,4,9,99,0"));
+        scheduler.connect(add, double).unwrap();
+        assert_eq!(
+            scheduler.connect(add, 2),
+            Err(ComputerError::UnknownMachine { id: 2 })
+        );
+
+        assert_eq!(scheduler.run(100), Status::Deadlock(vec![0, 1]));
+        assert_eq!(
+            scheduler.send(7, 5),
+            Err(ComputerError::UnknownMachine { id: 7 })
+        );
+        scheduler.send(add, 5).unwrap();
+        assert_eq!(scheduler.run(100), Status::Finished);
+        assert_eq!(scheduler.machines[double].computer.output, vec![12]);
+        assert_eq!(
+            scheduler.to_string(),
+            "machine 0: halted, 4 steps, 3 slices, 1 waits, 1 received\n\
+             machine 1: halted, 4 steps, 3 slices, 1 waits, 1 received\n"
+        );
+    }
+
+    #[test]
+    fn test_round_limit() {
+        let mut scheduler = Scheduler::new(10);
+        scheduler.add(Computer::from("1105,1,0"));
+        scheduler.add(Computer::from("3,0,99"));
+        assert_eq!(scheduler.run(5), Status::RoundLimit);
+        assert_eq!(scheduler.machines[0].stats.steps, 50);
+        assert_eq!(scheduler.machines[1].state, MachineState::Blocked);
+
+        let mut scheduler = Scheduler::new(10);
+        scheduler.add(Computer::from("1,0,0,0"));
+        assert_eq!(scheduler.run(5), Status::Finished);
+        assert!(matches!(
+            scheduler.machines[0].state,
+            MachineState::Failed(ComputerError::OutOfBounds { .. })
+        ));
+    }
+}
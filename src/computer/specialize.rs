@@ -0,0 +1,198 @@
+use std::fmt;
+use std::ops::ControlFlow;
+
+use super::{Computer, ComputerError, Isa};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecializeError {
+    /// The computer has state that can't be written into an image.
+    Unsupported(String),
+    /// The program failed before needing an unknown input.
+    Failed(ComputerError),
+}
+
+impl fmt::Display for SpecializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecializeError::Unsupported(reason) => write!(f, "Can't specialize: {reason}"),
+            SpecializeError::Failed(err) => write!(f, "Specializing failed: {err}"),
+        }
+    }
+}
+
+/// A program image with some of its inputs already consumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Specialization {
+    /// Runs from address 0 on the remaining inputs and behaves like the
+    /// original did on all of them, outputs produced so far included.
+    pub image: Vec<i32>,
+    /// Address the original stopped at.
+    pub entry: usize,
+    pub output: Vec<i32>,
+    /// Instructions run ahead of time.
+    pub steps: usize,
+}
+
+impl Specialization {
+    /// A fresh computer running `image` under the same ISA as `original`.
+    pub fn computer(&self, original: &Computer) -> Computer {
+        let mut computer = Computer::new_with(self.image.clone());
+        computer.isa = original.isa;
+        computer.arithmetic = original.arithmetic;
+        computer.validation = original.validation;
+        computer
+    }
+}
+
+/// Runs a clone of `computer` on `inputs` until it needs another input,
+/// halts, or has run `max_steps` instructions, then writes the state it got
+/// to into a new image.
+///
+/// Unless nothing happened yet, the image starts with a jump to a prologue
+/// appended after memory. The prologue restores the cells the jump
+/// overwrote, replays the outputs, sets the relative base and jumps to
+/// `entry`. Programs that expect to read zero past their end under
+/// `Isa::Day9` will see the prologue there instead.
+pub fn specialize(
+    computer: &Computer,
+    inputs: &[i32],
+    max_steps: usize,
+) -> Result<Specialization, SpecializeError> {
+    if !computer.devices.is_empty() || !computer.custom_ops.is_empty() {
+        return Err(SpecializeError::Unsupported(
+            "devices and custom instructions live outside memory".to_string(),
+        ));
+    }
+    let mut computer = computer.clone();
+    computer.inputs = inputs.iter().copied().collect();
+    computer.input = None;
+    let mut steps = 0;
+    while steps < max_steps && !(computer.at_input() && computer.inputs.is_empty()) {
+        let pointer = computer.pointer;
+        match computer.run_command() {
+            ControlFlow::Continue(_) => {}
+            ControlFlow::Break(Ok(_)) => {
+                // Stay on the instruction that halted.
+                computer.pointer = pointer;
+                steps += 1;
+                break;
+            }
+            ControlFlow::Break(Err(err)) => return Err(SpecializeError::Failed(err)),
+        }
+        steps += 1;
+    }
+
+    let mut image = computer.memory.to_vec();
+    let entry = computer.pointer;
+    let output = computer.output.clone();
+    if entry != 0 || !output.is_empty() || computer.relative_base != 0 {
+        if computer.isa == Isa::Day2 {
+            return Err(SpecializeError::Unsupported(
+                "Isa::Day2 has no jumps to reach the prologue with".to_string(),
+            ));
+        }
+        if image.len() < 3 {
+            image.resize(3, 0);
+        }
+        let prologue = image.len();
+        for address in 0..3 {
+            image.extend([1101, 0, image[address], address as i32]);
+        }
+        for &value in &output {
+            image.extend([104, value]);
+        }
+        if computer.relative_base != 0 {
+            image.extend([109, computer.relative_base]);
+        }
+        image.extend([1105, 1, entry as i32]);
+        image[..3].copy_from_slice(&[1105, 1, prologue as i32]);
+    }
+
+    Ok(Specialization {
+        image,
+        entry,
+        output,
+        steps,
+    })
+}
+
+impl Computer {
+    /// See `specialize::specialize`.
+    pub fn specialize(
+        &self,
+        inputs: &[i32],
+        max_steps: usize,
+    ) -> Result<Specialization, SpecializeError> {
+        specialize(self, inputs, max_steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mut computer: Computer, inputs: &[i32]) -> Vec<i32> {
+        computer.inputs.extend(inputs);
+        computer.run_program().unwrap();
+        computer.output
+    }
+
+    #[test]
+    fn test_specialize() {
+        // Reads `k`, then outputs `3 * k * x` for an input `x`.
+        let mut computer =
+            Computer::from("3,20,1002,20,3,20,3,21,2,20,21,22,4,22,99,0,0,0,0,0,0,0,0");
+        computer.isa = Isa::Day5;
+        let specialization = computer.specialize(&[2], 100).unwrap();
+        assert_eq!(specialization.entry, 6);
+        assert_eq!(specialization.steps, 2);
+        assert_eq!(specialization.image[20], 6);
+        assert_eq!(specialization.image[..3], [1105, 1, 23]);
+
+        let specialized = specialization.computer(&computer);
+        for x in -5..5 {
+            assert_eq!(
+                run(specialized.clone(), &[x]),
+                run(computer.clone(), &[2, x])
+            );
+        }
+
+        let untouched = computer.specialize(&[], 100).unwrap();
+        assert_eq!(untouched.image, computer.memory.to_vec());
+        assert_eq!(
+            computer
+                .specialize(&[2, 1], 100)
+                .unwrap()
+                .computer(&computer)
+                .run_program(),
+            Ok(3)
+        );
+    }
+
+    #[test]
+    fn test_prologue() {
+        // Outputs 7, then echoes an input through the relative base.
+        let mut computer = Computer::from("109,10,104,7,203,0,204,0,99,0,0");
+        computer.isa = Isa::Day9;
+        let specialization = computer.specialize(&[], 100).unwrap();
+        assert_eq!(specialization.output, vec![7]);
+        assert_eq!(
+            specialization.image[11..],
+            [1101, 0, 109, 0, 1101, 0, 10, 1, 1101, 0, 104, 2, 104, 7, 109, 10, 1105, 1, 4]
+        );
+        let specialized = specialization.computer(&computer);
+        assert_eq!(run(specialized, &[5]), vec![7, 5]);
+
+        let mut computer = Computer::from("1,0,0,0,99");
+        computer.isa = Isa::Day2;
+        assert!(matches!(
+            computer.specialize(&[], 100),
+            Err(SpecializeError::Unsupported(_))
+        ));
+        let err = Computer::from("98").specialize(&[], 100).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Specializing failed: Unknown opcode in 98 at pointer 0"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use super::{Computer, Digits, OpCode, ParameterMode};
+
+/// `constant + sum(coefficient * unknown)`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Linear {
+    pub constant: i64,
+    pub terms: BTreeMap<String, i64>,
+}
+
+/// A symbolic memory cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Linear(Linear),
+    /// Depends on the unknowns in a way that can't be expressed linearly,
+    /// e.g. it was read through a symbolic address, or a coefficient
+    /// overflowed.
+    Opaque,
+}
+
+impl Linear {
+    pub fn constant(value: i64) -> Self {
+        Self {
+            constant: value,
+            terms: BTreeMap::new(),
+        }
+    }
+
+    pub fn unknown(name: &str) -> Self {
+        Self {
+            constant: 0,
+            terms: BTreeMap::from([(name.to_string(), 1)]),
+        }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        self.terms.is_empty().then_some(self.constant)
+    }
+
+    /// Sum of two expressions, `None` on overflow.
+    pub fn add(&self, other: &Linear) -> Option<Linear> {
+        let mut res = self.clone();
+        res.constant = res.constant.checked_add(other.constant)?;
+        for (name, coefficient) in &other.terms {
+            let sum = res.terms.entry(name.clone()).or_default();
+            *sum = sum.checked_add(*coefficient)?;
+        }
+        res.terms.retain(|_, coefficient| *coefficient != 0);
+        Some(res)
+    }
+
+    /// `None` on overflow.
+    pub fn scale(&self, factor: i64) -> Option<Linear> {
+        let mut res = Linear::constant(self.constant.checked_mul(factor)?);
+        if factor != 0 {
+            res.terms = self
+                .terms
+                .iter()
+                .map(|(name, coefficient)| Some((name.clone(), coefficient.checked_mul(factor)?)))
+                .collect::<Option<_>>()?;
+        }
+        Some(res)
+    }
+
+    /// Product of two expressions, `None` if both depend on unknowns or on
+    /// overflow.
+    pub fn mul(&self, other: &Linear) -> Option<Linear> {
+        match (self.as_constant(), other.as_constant()) {
+            (Some(factor), _) => other.scale(factor),
+            (_, Some(factor)) => self.scale(factor),
+            _ => None,
+        }
+    }
+
+    /// `None` if an unknown has no value or on overflow.
+    pub fn eval(&self, values: &BTreeMap<String, i64>) -> Option<i64> {
+        self.terms
+            .iter()
+            .try_fold(self.constant, |acc, (name, coefficient)| {
+                acc.checked_add(coefficient.checked_mul(*values.get(name)?)?)
+            })
+    }
+
+    /// Finds values for the unknowns within `domains` so the expression equals
+    /// `goal`. The last unknown is solved for directly, the others are
+    /// enumerated in order, so the first solution has the smallest values.
+    pub fn solve(
+        &self,
+        goal: i64,
+        domains: &[(&str, RangeInclusive<i64>)],
+    ) -> Option<BTreeMap<String, i64>> {
+        if self
+            .terms
+            .keys()
+            .any(|name| !domains.iter().any(|(d, _)| d == name))
+        {
+            return None;
+        }
+        let mut values = BTreeMap::new();
+        self.solve_from(goal, domains, &mut values)
+            .then_some(values)
+    }
+
+    fn solve_from(
+        &self,
+        goal: i64,
+        domains: &[(&str, RangeInclusive<i64>)],
+        values: &mut BTreeMap<String, i64>,
+    ) -> bool {
+        match domains {
+            [] => self.eval(values) == Some(goal),
+            [(name, domain)] => {
+                let coefficient = self.terms.get(*name).copied().unwrap_or(0);
+                let rest = coefficient
+                    .checked_neg()
+                    .and_then(|factor| Linear::unknown(name).scale(factor))
+                    .and_then(|term| self.add(&term))
+                    .and_then(|rest| rest.eval(values));
+                let Some(rest) = rest else {
+                    return false;
+                };
+                let value = if coefficient == 0 {
+                    (rest == goal).then_some(*domain.start())
+                } else {
+                    goal.checked_sub(rest).and_then(|diff| {
+                        (diff.checked_rem(coefficient)? == 0)
+                            .then(|| diff.checked_div(coefficient))?
+                    })
+                };
+                match value.filter(|value| domain.contains(value)) {
+                    Some(value) => {
+                        values.insert(name.to_string(), value);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            [(name, domain), rest @ ..] => domain.clone().any(|value| {
+                values.insert(name.to_string(), value);
+                self.solve_from(goal, rest, values)
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Linear {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.constant)?;
+        for (name, coefficient) in &self.terms {
+            if *coefficient < 0 {
+                write!(f, " - {}*{name}", -coefficient)?;
+            } else {
+                write!(f, " + {coefficient}*{name}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Value {
+    pub fn linear(&self) -> Option<&Linear> {
+        match self {
+            Value::Linear(linear) => Some(linear),
+            Value::Opaque => None,
+        }
+    }
+
+    fn as_constant(&self) -> Option<i64> {
+        self.linear().and_then(Linear::as_constant)
+    }
+}
+
+/// Runs a program with some memory cells replaced by named unknowns. Only
+/// supports programs whose control flow and write addresses don't depend on
+/// the unknowns.
+#[derive(Debug, Clone)]
+pub struct SymbolicComputer {
+    pub pointer: usize,
+    pub memory: Vec<Value>,
+}
+
+impl SymbolicComputer {
+    /// Fails if an unknown's address is outside the program.
+    pub fn new(computer: &Computer, unknowns: &[(usize, &str)]) -> Result<Self, String> {
+        let mut memory: Vec<Value> = computer
+            .memory
+            .iter()
+            .map(|&value| Value::Linear(Linear::constant(value as i64)))
+            .collect();
+        let len = memory.len();
+        for (address, name) in unknowns {
+            let cell = memory.get_mut(*address).ok_or_else(|| {
+                format!("Unknown {name} at address {address} not in memory of size {len}")
+            })?;
+            *cell = Value::Linear(Linear::unknown(name));
+        }
+        Ok(Self {
+            pointer: computer.pointer,
+            memory,
+        })
+    }
+
+    fn concrete(&self, address: usize) -> Result<i64, String> {
+        self.memory
+            .get(address)
+            .ok_or_else(|| format!("Address {address} not in memory"))?
+            .as_constant()
+            .ok_or_else(|| format!("Value at {address} depends on unknowns"))
+    }
+
+    fn concrete_address(&self, address: usize) -> Result<usize, String> {
+        let value = self.concrete(address)?;
+        usize::try_from(value)
+            .ok()
+            .filter(|&value| value < self.memory.len())
+            .ok_or_else(|| format!("Address {value} not in memory"))
+    }
+
+    /// Value of a parameter, `Opaque` when read through a symbolic address.
+    fn read(&self, param: usize, mode: &ParameterMode) -> Result<Value, String> {
+        let param = self.pointer + param;
+        let value = self
+            .memory
+            .get(param)
+            .ok_or_else(|| format!("Address {param} not in memory"))?;
+        match mode {
+            ParameterMode::Immediate => Ok(value.clone()),
+            ParameterMode::Position => match value.as_constant() {
+                Some(_) => Ok(self.memory[self.concrete_address(param)?].clone()),
+                None => Ok(Value::Opaque),
+            },
+            ParameterMode::Relative => Err("Relative mode isn't supported symbolically".into()),
+        }
+    }
+
+    fn write_address(&self, param: usize, mode: &ParameterMode) -> Result<usize, String> {
+        let param = self.pointer + param;
+        match mode {
+            ParameterMode::Immediate => Ok(param),
+            ParameterMode::Position => self.concrete_address(param),
+            ParameterMode::Relative => Err("Relative mode isn't supported symbolically".into()),
+        }
+    }
+
+    pub fn run_program(&mut self) -> Result<&Value, String> {
+        const MAX_ITER: u32 = 1000;
+        for _ in 0..MAX_ITER {
+            let command = self.concrete(self.pointer)?;
+            if command < 0 {
+                return Err(format!("Invalid command {command} at {}", self.pointer));
+            }
+            let command = command as u32;
+            let op = OpCode::decode((command.digit(1) * 10) + command.digit(0))
+                .ok_or_else(|| format!("Unknown opcode {command} at {}", self.pointer))?;
+            let mode = |digit| {
+                ParameterMode::decode(command.digit(digit))
+                    .ok_or_else(|| format!("Invalid mode in {command} at {}", self.pointer))
+            };
+            let modes = [mode(2)?, mode(3)?, mode(4)?];
+
+            match op {
+                OpCode::Add | OpCode::Mul => {
+                    let a = self.read(1, &modes[0])?;
+                    let b = self.read(2, &modes[1])?;
+                    let to = self.write_address(3, &modes[2])?;
+                    self.memory[to] = match (a, b, &op) {
+                        (Value::Linear(a), Value::Linear(b), OpCode::Add) => {
+                            a.add(&b).map_or(Value::Opaque, Value::Linear)
+                        }
+                        (Value::Linear(a), Value::Linear(b), _) => {
+                            a.mul(&b).map_or(Value::Opaque, Value::Linear)
+                        }
+                        _ => Value::Opaque,
+                    };
+                    self.pointer += 4;
+                }
+                OpCode::Exit => return Ok(&self.memory[0]),
+                _ => {
+                    let a = self.read(1, &modes[0])?;
+                    let b = self.read(2, &modes[1])?;
+                    let (Some(a), Some(b)) = (a.as_constant(), b.as_constant()) else {
+                        return Err(format!("{op:?} at {} depends on unknowns", self.pointer));
+                    };
+                    match op {
+                        OpCode::JumpTrue | OpCode::JumpFalse => {
+                            if (a != 0) == (op == OpCode::JumpTrue) {
+                                self.pointer = b as usize;
+                            } else {
+                                self.pointer += 3;
+                            }
+                        }
+                        OpCode::LessThan | OpCode::Equal => {
+                            let to = self.write_address(3, &modes[2])?;
+                            let res = if op == OpCode::LessThan {
+                                a < b
+                            } else {
+                                a == b
+                            };
+                            self.memory[to] = Value::Linear(Linear::constant(res as i64));
+                            self.pointer += 4;
+                        }
+                        _ => return Err(format!("{op:?} isn't supported symbolically")),
+                    }
+                }
+            }
+        }
+        Err(format!("Hit max iterations {MAX_ITER}"))
+    }
+}
+
+impl Computer {
+    /// Symbolic copy of this computer with the given cells as unknowns.
+    pub fn symbolic(&self, unknowns: &[(usize, &str)]) -> Result<SymbolicComputer, String> {
+        SymbolicComputer::new(self, unknowns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear() {
+        let noun = Linear::unknown("noun");
+        let verb = Linear::unknown("verb");
+        let expr = noun
+            .scale(100)
+            .and_then(|noun| noun.add(&verb))
+            .and_then(|expr| expr.add(&Linear::constant(7)))
+            .unwrap();
+        assert_eq!(expr.to_string(), "7 + 100*noun + 1*verb");
+        assert_eq!(expr.mul(&verb), None);
+        assert_eq!(expr.scale(i64::MAX), None);
+        assert_eq!(Linear::constant(i64::MAX).add(&Linear::constant(1)), None);
+
+        let solution = expr
+            .solve(1209, &[("noun", 0..=99), ("verb", 0..=99)])
+            .unwrap();
+        assert_eq!(solution["noun"], 12);
+        assert_eq!(solution["verb"], 2);
+        assert_eq!(
+            expr.solve(100_000, &[("noun", 0..=99), ("verb", 0..=99)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        // memory[0] = 100 * noun + verb, the first add reads through the
+        // unknowns but its result is overwritten
+        let computer = Computer::from("1,0,0,3,2,1,13,3,1,3,2,0,99,100");
+        let mut symbolic = computer.symbolic(&[(1, "noun"), (2, "verb")]).unwrap();
+        let res = symbolic.run_program().unwrap();
+        assert_eq!(res.linear().unwrap().to_string(), "0 + 100*noun + 1*verb");
+
+        let computer = Computer::from("1,0,0,0,99");
+        let mut symbolic = computer.symbolic(&[(1, "noun"), (2, "verb")]).unwrap();
+        assert_eq!(symbolic.run_program(), Ok(&Value::Opaque));
+        assert_eq!(
+            computer.symbolic(&[(10, "x")]).unwrap_err(),
+            "Unknown x at address 10 not in memory of size 5"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+
+use super::{Computer, Isa};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub program: Vec<i32>,
+    pub input: Vec<i32>,
+    pub output: Option<Vec<i32>>,
+    pub memory: Vec<(usize, i32)>,
+    pub error: Option<String>,
+    pub isa: Option<Isa>,
+}
+
+impl TestCase {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            program: vec![],
+            input: vec![],
+            output: None,
+            memory: vec![],
+            error: None,
+            isa: None,
+        }
+    }
+
+    /// Runs the case and returns every expectation that didn't hold.
+    pub fn run(&self) -> Vec<String> {
+        let mut computer = Computer::new_with(self.program.clone());
+        computer.isa = self.isa.unwrap_or(computer.isa);
+        computer.inputs.extend(&self.input);
+        let result = computer.run_program();
+
+        let mut problems = vec![];
+        match (&result, &self.error) {
+            (Ok(_), Some(expected)) => problems.push(format!("expected error {expected}")),
+            (Err(err), Some(expected)) if err.name() != expected => {
+                problems.push(format!("expected error {expected}, got {}", err.name()))
+            }
+            (Err(err), None) => problems.push(format!("unexpected error: {err}")),
+            _ => {}
+        }
+        if let Some(output) = &self.output {
+            if computer.output != *output {
+                problems.push(format!(
+                    "expected output {output:?}, got {:?}",
+                    computer.output
+                ));
+            }
+        }
+        for &(address, value) in &self.memory {
+            let actual = computer.memory.get(address).copied();
+            if actual != Some(value) {
+                problems.push(format!(
+                    "expected memory[{address}] = {value}, got {actual:?}"
+                ));
+            }
+        }
+        problems
+    }
+}
+
+fn parse_values<T: std::str::FromStr>(value: &str) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().map_err(|_| format!("Invalid value {value}")))
+        .collect()
+}
+
+fn parse_cell(cell: &str) -> Result<(usize, i32), String> {
+    let (address, value) = cell
+        .split_once(':')
+        .ok_or_else(|| format!("Expected address:value, got {cell}"))?;
+    let address = address.trim();
+    let value = value.trim();
+    Ok((
+        address
+            .parse()
+            .map_err(|_| format!("Invalid address {address}"))?,
+        value
+            .parse()
+            .map_err(|_| format!("Invalid value {value}"))?,
+    ))
+}
+
+fn parse_isa(value: &str) -> Result<Isa, String> {
+    match value.to_lowercase().as_str() {
+        "day2" => Ok(Isa::Day2),
+        "day5" => Ok(Isa::Day5),
+        "day9" => Ok(Isa::Day9),
+        _ => Err(format!("Unknown isa {value}")),
+    }
+}
+
+/// Parses a case file, a list of named sections:
+///
+/// ```text
+/// # Comments start with '#'.
+/// [equal to 8]
+/// program = 3,9,8,9,10,9,4,9,99,-1,8
+/// input = 8
+/// output = 1
+/// ```
+///
+/// Keys are `program` (required), `input` (values consumed in order),
+/// `output` (the full output sequence), `memory` (`address:value` cells of the
+/// final memory), `error` (the `ComputerError` variant the run stops with) and
+/// `isa` (`day2`, `day5` or `day9`). Checks that aren't given are skipped.
+///
+/// Errors carry the 1-based line number.
+pub fn parse(text: &str) -> Result<Vec<TestCase>, String> {
+    let mut cases: Vec<TestCase> = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        let at_line = |err: String| format!("line {}: {err}", index + 1);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            cases.push(TestCase::new(name.trim()));
+            continue;
+        }
+        let case = cases
+            .last_mut()
+            .ok_or_else(|| at_line("Expected [name] before the first key".to_string()))?;
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| at_line(format!("Expected key = value, got {line}")))?;
+        let value = value.trim();
+        match key.trim() {
+            "program" => case.program = parse_values(value).map_err(at_line)?,
+            "input" => case.input = parse_values(value).map_err(at_line)?,
+            "output" => case.output = Some(parse_values(value).map_err(at_line)?),
+            "memory" => {
+                case.memory = value
+                    .split(',')
+                    .map(parse_cell)
+                    .collect::<Result<_, _>>()
+                    .map_err(at_line)?
+            }
+            "error" => case.error = Some(value.to_string()),
+            "isa" => case.isa = Some(parse_isa(value).map_err(at_line)?),
+            key => return Err(at_line(format!("Unknown key {key}"))),
+        }
+    }
+    match cases.iter().find(|case| case.program.is_empty()) {
+        Some(case) => Err(format!("Case {} has no program", case.name)),
+        None => Ok(cases),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub name: String,
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub passed: usize,
+    pub failures: Vec<Failure>,
+}
+
+impl Report {
+    pub fn run(cases: &[TestCase]) -> Self {
+        let failures: Vec<_> = cases
+            .iter()
+            .map(|case| Failure {
+                name: case.name.clone(),
+                problems: case.run(),
+            })
+            .filter(|failure| !failure.problems.is_empty())
+            .collect();
+        Self {
+            passed: cases.len() - failures.len(),
+            failures,
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for failure in &self.failures {
+            for problem in &failure.problems {
+                writeln!(f, "FAIL {}: {problem}", failure.name)?;
+            }
+        }
+        writeln!(f, "{} passed, {} failed", self.passed, self.failures.len())
+    }
+}
+
+/// Parses and runs a case file.
+pub fn run_cases(text: &str) -> Result<Report, String> {
+    Ok(Report::run(&parse(text)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let cases = parse(
+            "# Echo\n[echo]\nprogram = 3,0,4,0,99\ninput = 5\noutput = 5\nmemory = 0:5, 4:99\n\n\
+             [no input]\nprogram = 3,0,99\nerror = MissingInput\nisa = day5\n",
+        )
+        .unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].input, vec![5]);
+        assert_eq!(cases[0].memory, vec![(0, 5), (4, 99)]);
+        assert_eq!(cases[1].isa, Some(Isa::Day5));
+
+        assert_eq!(
+            parse("program = 99"),
+            Err("line 1: Expected [name] before the first key".to_string())
+        );
+        assert_eq!(
+            parse("[a]\nprogram = 99\nouput = 1"),
+            Err("line 3: Unknown key ouput".to_string())
+        );
+        assert_eq!(
+            parse("[a]\ninput = 1"),
+            Err("Case a has no program".to_string())
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let report = run_cases(
+            "[sum]\nprogram = 3,9,3,10,1,9,10,0,99,0,0\ninput = 2,3\nmemory = 0:5\n\
+             [wrong]\nprogram = 104,1,99\noutput = 2\n\
+             [no error]\nprogram = 99\nerror = MissingInput\n",
+        )
+        .unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.to_string(),
+            "FAIL wrong: expected output [2], got [1]\n\
+             FAIL no error: expected error MissingInput\n\
+             1 passed, 2 failed\n"
+        );
+    }
+}
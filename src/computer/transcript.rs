@@ -0,0 +1,283 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::str::FromStr;
+
+use super::{Computer, ComputerError};
+
+/// Something a run did that is visible from outside, with the step it
+/// happened at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Input { step: usize, value: i32 },
+    Output { step: usize, value: i32 },
+    Halt { step: usize },
+}
+
+impl Event {
+    pub fn step(&self) -> usize {
+        match self {
+            Event::Input { step, .. } | Event::Output { step, .. } | Event::Halt { step } => *step,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Input { step, value } => write!(f, "{step} in {value}"),
+            Event::Output { step, value } => write!(f, "{step} out {value}"),
+            Event::Halt { step } => write!(f, "{step} halt"),
+        }
+    }
+}
+
+impl FromStr for Event {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.split_whitespace().collect();
+        fn number<T: FromStr>(part: &str) -> Result<T, String> {
+            part.parse().map_err(|_| format!("Invalid number {part}"))
+        }
+        match parts[..] {
+            [step, "in", value] => Ok(Event::Input {
+                step: number(step)?,
+                value: number(value)?,
+            }),
+            [step, "out", value] => Ok(Event::Output {
+                step: number(step)?,
+                value: number(value)?,
+            }),
+            [step, "halt"] => Ok(Event::Halt {
+                step: number(step)?,
+            }),
+            _ => Err(format!("Invalid event {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// At `step` the run did `actual` where the transcript expected the next
+    /// event, or its end.
+    Mismatch {
+        step: usize,
+        expected: Option<Event>,
+        actual: String,
+    },
+    Failed(ComputerError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Mismatch {
+                step,
+                expected: Some(expected),
+                actual,
+            } => write!(f, "Step {step}: expected `{expected}`, got {actual}"),
+            ReplayError::Mismatch {
+                step,
+                expected: None,
+                actual,
+            } => write!(f, "Step {step}: expected end of transcript, got {actual}"),
+            ReplayError::Failed(err) => write!(f, "Replay failed: {err}"),
+        }
+    }
+}
+
+/// Every input consumed and output produced by a run, one event per line in
+/// text form, e.g. `12 in 5`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Transcript {
+    pub events: Vec<Event>,
+}
+
+impl Transcript {
+    /// Runs `computer` to completion, recording its I/O. Fails if the run
+    /// fails or takes more than `max_steps` instructions.
+    pub fn record(computer: &mut Computer, max_steps: u32) -> Result<Self, ComputerError> {
+        let mut events = vec![];
+        for step in 0..max_steps as usize {
+            let input = if computer.at_input() {
+                computer.inputs.front().copied().or(computer.input)
+            } else {
+                None
+            };
+            let outputs = computer.output.len();
+            let res = computer.run_command();
+            if let ControlFlow::Break(Err(err)) = res {
+                return Err(err);
+            }
+            if let Some(value) = input {
+                events.push(Event::Input { step, value });
+            }
+            for &value in &computer.output[outputs..] {
+                events.push(Event::Output { step, value });
+            }
+            if let ControlFlow::Break(_) = res {
+                events.push(Event::Halt { step });
+                return Ok(Self { events });
+            }
+        }
+        Err(ComputerError::MaxIterations(max_steps))
+    }
+
+    /// Runs `computer` feeding it the recorded inputs, and checks that it
+    /// asks for input, produces output and halts at exactly the recorded
+    /// steps.
+    pub fn replay(&self, computer: &mut Computer) -> Result<(), ReplayError> {
+        let mut events = self.events.iter().copied().peekable();
+        for step in 0.. {
+            let mismatch = |expected: Option<Event>, actual: &str| ReplayError::Mismatch {
+                step,
+                expected,
+                actual: actual.to_string(),
+            };
+            let next = events.peek().copied();
+            match next {
+                None => return Err(mismatch(None, "another step")),
+                Some(event) if event.step() < step => return Err(mismatch(next, "nothing")),
+                _ => {}
+            }
+
+            match (computer.at_input(), next) {
+                (true, Some(Event::Input { step: at, value })) if at == step => {
+                    computer.inputs.push_front(value);
+                    events.next();
+                }
+                (true, _) => return Err(mismatch(next, "input")),
+                (false, Some(Event::Input { step: at, .. })) if at == step => {
+                    return Err(mismatch(next, "no input"))
+                }
+                _ => {}
+            }
+
+            let outputs = computer.output.len();
+            let res = computer.run_command();
+            if let ControlFlow::Break(Err(err)) = res {
+                return Err(ReplayError::Failed(err));
+            }
+            for &value in &computer.output[outputs..] {
+                let actual = Event::Output { step, value };
+                let next = events.next();
+                if next != Some(actual) {
+                    return Err(mismatch(next, &format!("`{actual}`")));
+                }
+            }
+
+            let next = events.peek().copied();
+            match (res, next) {
+                (ControlFlow::Break(_), Some(Event::Halt { step: at })) if at == step => {
+                    events.next();
+                    return match events.next() {
+                        Some(event) => Err(mismatch(Some(event), "end of run")),
+                        None => Ok(()),
+                    };
+                }
+                (ControlFlow::Break(_), _) => return Err(mismatch(next, "halt")),
+                (_, Some(event)) if event.step() == step => {
+                    return Err(mismatch(next, "no such event"))
+                }
+                _ => {}
+            }
+        }
+        unreachable!()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|err| err.to_string())?
+            .parse()
+    }
+}
+
+impl fmt::Display for Transcript {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for event in &self.events {
+            writeln!(f, "{event}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Transcript {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let events = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                line.parse()
+                    .map_err(|err| format!("line {}: {err}", index + 1))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { events })
+    }
+}
+
+impl Computer {
+    pub fn record(&mut self, max_steps: u32) -> Result<Transcript, ComputerError> {
+        Transcript::record(self, max_steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Echoes two inputs.
+    const ECHO: &str = "3,0,4,0,3,1,4,1,99";
+
+    #[test]
+    fn test_record() {
+        let mut computer = Computer::from(ECHO);
+        computer.inputs.extend([5, 6]);
+        let transcript = computer.record(100).unwrap();
+        let text = "0 in 5\n1 out 5\n2 in 6\n3 out 6\n4 halt\n";
+        assert_eq!(transcript.to_string(), text);
+        assert_eq!(text.parse(), Ok(transcript.clone()));
+        assert_eq!(
+            "0 in 5\n1 sideways\n".parse::<Transcript>(),
+            Err("line 2: Invalid event 1 sideways".to_string())
+        );
+
+        let path = std::env::temp_dir().join("intcode_transcript_test.txt");
+        transcript.save(&path).unwrap();
+        assert_eq!(Transcript::load(&path), Ok(transcript));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay() {
+        let transcript: Transcript = "0 in 5\n1 out 5\n2 in 6\n3 out 6\n4 halt\n"
+            .parse()
+            .unwrap();
+        assert_eq!(transcript.replay(&mut Computer::from(ECHO)), Ok(()));
+
+        // Doubles the second input before echoing it.
+        let mut changed = Computer::from("3,0,4,0,3,1,1002,1,2,1,4,1,99");
+        assert_eq!(
+            transcript.replay(&mut changed),
+            Err(ReplayError::Mismatch {
+                step: 3,
+                expected: Some(Event::Output { step: 3, value: 6 }),
+                actual: "no such event".to_string()
+            })
+        );
+
+        let err = transcript
+            .replay(&mut Computer::from("3,0,99"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Step 1: expected `1 out 5`, got halt");
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::cfg::ControlFlowGraph;
+use super::disasm::Instruction;
+use super::memory::Cells;
+use super::sandbox::Sandbox;
+use super::{Computer, Isa, OpCode, ParameterMode};
+
+/// Instructions are compiled at this depth inside the generated `run`.
+const INDENT: &str = "                ";
+
+/// Rust source for the operand `param` of `instruction`, or `None` if it
+/// can't be resolved ahead of time.
+fn operand(instruction: &Instruction, param: usize, len: usize) -> Option<String> {
+    let value = instruction.params[param];
+    match instruction.modes[param] {
+        ParameterMode::Position if (0..len as i32).contains(&value) => {
+            Some(format!("computer.memory[{value}]"))
+        }
+        ParameterMode::Immediate => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// Address written by `instruction`. Immediate-mode destinations are left
+/// to the interpreter, which knows about `Validation`.
+fn destination(instruction: &Instruction, len: usize) -> Option<usize> {
+    let param = instruction.destination()?;
+    let value = instruction.params[param];
+    match instruction.modes[param] {
+        ParameterMode::Position if (0..len as i32).contains(&value) => Some(value as usize),
+        _ => None,
+    }
+}
+
+/// The body of the match arm running `instruction`, evaluating to `true`
+/// when it ran or `false` to hand it to the interpreter. Nothing is changed
+/// before deciding.
+fn compile(instruction: &Instruction, len: usize) -> Option<String> {
+    let operand = |param| operand(instruction, param, len);
+    let next = instruction.next();
+    let lines = match instruction.op {
+        OpCode::Add | OpCode::Mul => {
+            let checked = match instruction.op {
+                OpCode::Add => "checked_add",
+                _ => "checked_mul",
+            };
+            vec![
+                format!("let a = {};", operand(0)?),
+                format!("let b = {};", operand(1)?),
+                format!("match i32::{checked}(a, b) {{"),
+                "    Some(res) => {".to_string(),
+                format!(
+                    "        computer.memory[{}] = res;",
+                    destination(instruction, len)?
+                ),
+                format!("        computer.pointer = {next};"),
+                "        true".to_string(),
+                "    }".to_string(),
+                "    None => false,".to_string(),
+                "}".to_string(),
+            ]
+        }
+        OpCode::Input => vec![
+            "match computer.inputs.pop_front().or(computer.input) {".to_string(),
+            "    Some(value) => {".to_string(),
+            format!(
+                "        computer.memory[{}] = value;",
+                destination(instruction, len)?
+            ),
+            format!("        computer.pointer = {next};"),
+            "        true".to_string(),
+            "    }".to_string(),
+            "    None => false,".to_string(),
+            "}".to_string(),
+        ],
+        OpCode::Output => vec![
+            format!("let value = {};", operand(0)?),
+            "computer.output.push(value);".to_string(),
+            format!("computer.output_pointers.push({});", instruction.address),
+            format!("computer.pointer = {next};"),
+            "true".to_string(),
+        ],
+        OpCode::JumpTrue | OpCode::JumpFalse => {
+            let condition = match instruction.op {
+                OpCode::JumpTrue => "a != 0",
+                _ => "a == 0",
+            };
+            let target = match instruction.modes[1] {
+                ParameterMode::Immediate if instruction.params[1] >= 0 => operand(1)?,
+                ParameterMode::Immediate => return None,
+                _ => format!("{} as usize", operand(1)?),
+            };
+            vec![
+                format!("let a = {};", operand(0)?),
+                format!("computer.pointer = if {condition} {{ {target} }} else {{ {next} }};"),
+                "true".to_string(),
+            ]
+        }
+        OpCode::LessThan | OpCode::Equal => {
+            let compare = match instruction.op {
+                OpCode::LessThan => "<",
+                _ => "==",
+            };
+            vec![
+                format!("let a = {};", operand(0)?),
+                format!("let b = {};", operand(1)?),
+                format!(
+                    "computer.memory[{}] = i32::from(a {compare} b);",
+                    destination(instruction, len)?
+                ),
+                format!("computer.pointer = {next};"),
+                "true".to_string(),
+            ]
+        }
+        OpCode::AdjustBase => vec![
+            format!("let a = {};", operand(0)?),
+            "match i32::checked_add(computer.relative_base, a) {".to_string(),
+            "    Some(res) => {".to_string(),
+            "        computer.relative_base = res;".to_string(),
+            format!("        computer.pointer = {next};"),
+            "        true".to_string(),
+            "    }".to_string(),
+            "    None => false,".to_string(),
+            "}".to_string(),
+        ],
+        OpCode::Exit => vec!["return Ok(computer.memory[0]);".to_string()],
+    };
+    Some(lines.join("\n"))
+}
+
+/// Emits a Rust module with a `run(&mut Computer)` function behaving like
+/// `Computer::run_program` on `memory`, minus the step limit and loop
+/// detection. `path` is the module path of `computer` as seen from where the
+/// output is placed, e.g. `advent_2029::computer`.
+///
+/// Statically reachable instructions become arms of a match on the pointer,
+/// each guarded by a check that its cells still hold their original values.
+/// Self-modified code, relative-mode operands, indirect jump targets and
+/// anything else that can't be resolved ahead of time run through
+/// `Computer::run_command`.
+pub fn transpile<M: Cells + ?Sized>(memory: &M, isa: Isa, path: &str) -> String {
+    let cfg = ControlFlowGraph::new(memory, 0);
+    let instructions: BTreeMap<_, _> = cfg
+        .blocks
+        .values()
+        .flat_map(|block| &block.instructions)
+        .filter(|instruction| isa.supports(&instruction.op))
+        .filter(|instruction| {
+            instruction.modes[..instruction.op.arity()]
+                .iter()
+                .all(|mode| isa.supports_mode(mode))
+        })
+        .map(|instruction| (instruction.address, instruction))
+        .collect();
+
+    let mut res = String::new();
+    writeln!(res, "// Generated by `Computer::transpile`, do not edit.").unwrap();
+    writeln!(res, "use std::ops::ControlFlow;\n").unwrap();
+    writeln!(res, "use {path}::{{Computer, ComputerError, Isa}};\n").unwrap();
+    writeln!(
+        res,
+        "pub fn run(computer: &mut Computer) -> Result<i32, ComputerError> {{"
+    )
+    .unwrap();
+    writeln!(
+        res,
+        "    let compiled = computer.isa == Isa::{isa:?} && computer.is_plain() && computer.len() >= {};",
+        memory.len()
+    )
+    .unwrap();
+    writeln!(res, "    loop {{").unwrap();
+    writeln!(res, "        let done = compiled").unwrap();
+    writeln!(res, "            && match computer.pointer {{").unwrap();
+    for (address, instruction) in instructions {
+        let Some(body) = compile(instruction, memory.len()) else {
+            continue;
+        };
+        let cells = (address..instruction.next())
+            .map(|address| memory.cell(address).unwrap())
+            .collect::<Vec<_>>();
+        writeln!(res, "{INDENT}// {instruction}").unwrap();
+        writeln!(
+            res,
+            "{INDENT}{address} if computer.memory.matches({address}, &{cells:?}) => {{"
+        )
+        .unwrap();
+        for line in body.lines() {
+            writeln!(res, "{INDENT}    {line}").unwrap();
+        }
+        writeln!(res, "{INDENT}}}").unwrap();
+    }
+    writeln!(res, "{INDENT}_ => false,").unwrap();
+    writeln!(res, "            }};").unwrap();
+    writeln!(res, "        if !done {{").unwrap();
+    writeln!(
+        res,
+        "            if let ControlFlow::Break(res) = computer.run_command() {{"
+    )
+    .unwrap();
+    writeln!(
+        res,
+        "                return res.map(|_| computer.memory[0]);"
+    )
+    .unwrap();
+    writeln!(res, "            }}").unwrap();
+    writeln!(res, "        }}").unwrap();
+    writeln!(res, "    }}").unwrap();
+    writeln!(res, "}}").unwrap();
+    res
+}
+
+impl Computer {
+    /// See `transpile`.
+    pub fn transpile(&self, path: &str) -> String {
+        transpile(&self.memory, self.isa, path)
+    }
+
+    /// True when nothing but memory, I/O and the relative base affects a run,
+    /// so transpiled code may access memory directly: no devices, gas, custom
+    /// instructions or sandbox limits.
+    pub fn is_plain(&self) -> bool {
+        self.devices.is_empty()
+            && self.gas.is_none()
+            && self.custom_ops.is_empty()
+            && self.sandbox == Sandbox::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::gas::CostTable;
+
+    /// Regenerate with
+    /// `Computer::from(DAY5).transpile("crate::computer")` after changing
+    /// `transpile`.
+    mod day05 {
+        include!("transpile/day05.rs");
+    }
+
+    // Outputs 999 below 8, 1000 for 8 and 1001 above.
+    const DAY5: &str = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+                        1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+                        999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+
+    fn check(computer: &Computer) {
+        let mut interpreted = computer.clone();
+        let mut transpiled = computer.clone();
+        assert_eq!(
+            day05::run(&mut transpiled),
+            interpreted.run_program(),
+            "{computer:?}"
+        );
+        assert_eq!(transpiled.output, interpreted.output);
+        assert_eq!(transpiled.output_pointers, interpreted.output_pointers);
+        assert_eq!(transpiled.memory, interpreted.memory);
+    }
+
+    #[test]
+    fn test_transpile() {
+        let mut computer = Computer::from(DAY5);
+        computer.isa = Isa::Day5;
+        assert_eq!(
+            computer.transpile("crate::computer"),
+            include_str!("transpile/day05.rs")
+        );
+        assert!(computer.is_plain());
+        for input in -2..12 {
+            computer.input = Some(input);
+            check(&computer);
+        }
+        computer.input = None;
+        check(&computer);
+    }
+
+    #[test]
+    fn test_fallback() {
+        let mut computer = Computer::from(DAY5);
+        computer.isa = Isa::Day5;
+        computer.input = Some(5);
+
+        // Compares against 4 instead of 8, so `EQ` no longer matches its
+        // compiled arm.
+        computer.memory[4] = 4;
+        check(&computer);
+
+        computer.memory[4] = 8;
+        computer.meter(1000, CostTable::default());
+        assert!(!computer.is_plain());
+        check(&computer);
+
+        // Day 2 has none of the compiled instructions.
+        computer.gas = None;
+        computer.isa = Isa::Day2;
+        check(&computer);
+
+        // An overflowing relative base goes to the interpreter.
+        let mut computer = Computer::from("109,2147483647,109,1,99");
+        computer.isa = Isa::Day9;
+        assert!(computer
+            .transpile("crate::computer")
+            .contains("match i32::checked_add(computer.relative_base, a) {"));
+    }
+}
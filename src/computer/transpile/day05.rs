@@ -0,0 +1,136 @@
+// Generated by `Computer::transpile`, do not edit.
+use std::ops::ControlFlow;
+
+use crate::computer::{Computer, ComputerError, Isa};
+
+pub fn run(computer: &mut Computer) -> Result<i32, ComputerError> {
+    let compiled = computer.isa == Isa::Day5 && computer.is_plain() && computer.len() >= 47;
+    loop {
+        let done = compiled
+            && match computer.pointer {
+                // 0000: IN [21]
+                0 if computer.memory.matches(0, &[3, 21]) => {
+                    match computer.inputs.pop_front().or(computer.input) {
+                        Some(value) => {
+                            computer.memory[21] = value;
+                            computer.pointer = 2;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                // 0002: EQ [21], 8, [20]
+                2 if computer.memory.matches(2, &[1008, 21, 8, 20]) => {
+                    let a = computer.memory[21];
+                    let b = 8;
+                    computer.memory[20] = i32::from(a == b);
+                    computer.pointer = 6;
+                    true
+                }
+                // 0006: JT [20], 22
+                6 if computer.memory.matches(6, &[1005, 20, 22]) => {
+                    let a = computer.memory[20];
+                    computer.pointer = if a != 0 { 22 } else { 9 };
+                    true
+                }
+                // 0009: LT 8, [21], [20]
+                9 if computer.memory.matches(9, &[107, 8, 21, 20]) => {
+                    let a = 8;
+                    let b = computer.memory[21];
+                    computer.memory[20] = i32::from(a < b);
+                    computer.pointer = 13;
+                    true
+                }
+                // 0013: JF [20], 31
+                13 if computer.memory.matches(13, &[1006, 20, 31]) => {
+                    let a = computer.memory[20];
+                    computer.pointer = if a == 0 { 31 } else { 16 };
+                    true
+                }
+                // 0016: JF 0, 36
+                16 if computer.memory.matches(16, &[1106, 0, 36]) => {
+                    let a = 0;
+                    computer.pointer = if a == 0 { 36 } else { 19 };
+                    true
+                }
+                // 0022: MUL [21], 125, [20]
+                22 if computer.memory.matches(22, &[1002, 21, 125, 20]) => {
+                    let a = computer.memory[21];
+                    let b = 125;
+                    match i32::checked_mul(a, b) {
+                        Some(res) => {
+                            computer.memory[20] = res;
+                            computer.pointer = 26;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                // 0026: OUT [20]
+                26 if computer.memory.matches(26, &[4, 20]) => {
+                    let value = computer.memory[20];
+                    computer.output.push(value);
+                    computer.output_pointers.push(26);
+                    computer.pointer = 28;
+                    true
+                }
+                // 0028: JT 1, 46
+                28 if computer.memory.matches(28, &[1105, 1, 46]) => {
+                    let a = 1;
+                    computer.pointer = if a != 0 { 46 } else { 31 };
+                    true
+                }
+                // 0031: OUT 999
+                31 if computer.memory.matches(31, &[104, 999]) => {
+                    let value = 999;
+                    computer.output.push(value);
+                    computer.output_pointers.push(31);
+                    computer.pointer = 33;
+                    true
+                }
+                // 0033: JT 1, 46
+                33 if computer.memory.matches(33, &[1105, 1, 46]) => {
+                    let a = 1;
+                    computer.pointer = if a != 0 { 46 } else { 36 };
+                    true
+                }
+                // 0036: ADD 1000, 1, [20]
+                36 if computer.memory.matches(36, &[1101, 1000, 1, 20]) => {
+                    let a = 1000;
+                    let b = 1;
+                    match i32::checked_add(a, b) {
+                        Some(res) => {
+                            computer.memory[20] = res;
+                            computer.pointer = 40;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                // 0040: OUT [20]
+                40 if computer.memory.matches(40, &[4, 20]) => {
+                    let value = computer.memory[20];
+                    computer.output.push(value);
+                    computer.output_pointers.push(40);
+                    computer.pointer = 42;
+                    true
+                }
+                // 0042: JT 1, 46
+                42 if computer.memory.matches(42, &[1105, 1, 46]) => {
+                    let a = 1;
+                    computer.pointer = if a != 0 { 46 } else { 45 };
+                    true
+                }
+                // 0046: HALT
+                46 if computer.memory.matches(46, &[99]) => {
+                    return Ok(computer.memory[0]);
+                }
+                _ => false,
+            };
+        if !done {
+            if let ControlFlow::Break(res) = computer.run_command() {
+                return res.map(|_| computer.memory[0]);
+            }
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use itertools::Itertools;
 
-use crate::computer::Computer;
+use crate::computer::batch::Variant;
+use crate::computer::{Computer, ComputerError, Isa};
 
 #[aoc_generator(day02)]
 fn generator(input: &str) -> Computer {
-    Computer::from(input)
+    let mut computer = Computer::from(input);
+    computer.isa = Isa::Day2;
+    computer
 }
 
 #[aoc(day02, part1)]
@@ -16,35 +19,8 @@ fn part_1(input: &Computer) -> i32 {
 }
 
 fn solve(input: &Computer) -> i32 {
-    let mut input = input.memory.to_owned();
-    let mut i = 0;
-    while i < input.len() {
-        match input[i] {
-            99 => {
-                // println!("99: break");
-                break;
-            }
-            1 => {
-                let a = input[input[i + 1] as usize];
-                let b = input[input[i + 2] as usize];
-                let to = input[i + 3] as usize;
-                input[to] = a + b;
-                // println!("1: {a} + {b} into {to}");
-                i += 4;
-            }
-            2 => {
-                let a = input[input[i + 1] as usize];
-                let b = input[input[i + 2] as usize];
-                let to = input[i + 3] as usize;
-                input[to] = a * b;
-                // println!("2: {a} * {b} into {to}");
-                i += 4;
-            }
-            _ => todo!(),
-        }
-    }
-    // println!("end: {:?}", &input);
-    input[0]
+    let mut computer = input.clone();
+    computer.run_program().expect("Program failed")
 }
 
 #[aoc(day02, part1, Computer)]
@@ -56,7 +32,10 @@ fn part_1_computer(computer: &Computer) -> i32 {
     }
 }
 
-fn solve_computer(computer: &Computer, parameters: Option<(i32, i32)>) -> Result<i32, String> {
+fn solve_computer(
+    computer: &Computer,
+    parameters: Option<(i32, i32)>,
+) -> Result<i32, ComputerError> {
     let mut computer = computer.clone();
     if let Some(parameters) = parameters {
         computer.set_parameters(parameters)?;
@@ -67,31 +46,36 @@ fn solve_computer(computer: &Computer, parameters: Option<(i32, i32)>) -> Result
 const GOAL: i32 = 19690720;
 #[aoc(day02, part2)]
 fn part_2(computer: &Computer) -> i32 {
-    let mut flip = false;
-    let pair = (0..=99).combinations(2).find(|parameters| {
-        let noun = parameters[0];
-        let verb = parameters[1];
-        if let Ok(res) = solve_computer(computer, Some((noun, verb))) {
-            if res == GOAL {
-                return true;
-            }
-        }
-        if let Ok(res) = solve_computer(computer, Some((verb, noun))) {
-            if res == GOAL {
-                flip = true;
-                return true;
-            }
-        }
-        false
-    });
-
-    if let Some(pair) = pair {
-        let mut noun = pair[0];
-        let mut verb = pair[1];
-        if flip {
-            noun = pair[1];
-            verb = pair[0];
-        }
+    solve_symbolic(computer, GOAL).unwrap_or_else(|| search(computer))
+}
+
+/// Expresses `memory[0]` in terms of noun and verb and solves for `goal`.
+/// Returns `None` if the program isn't linear in its parameters.
+fn solve_symbolic(computer: &Computer, goal: i32) -> Option<i32> {
+    let mut symbolic = computer.symbolic(&[(1, "noun"), (2, "verb")]).ok()?;
+    let res = symbolic.run_program().ok()?.linear()?.clone();
+    let solution = res.solve(goal as i64, &[("noun", 0..=99), ("verb", 0..=99)])?;
+    let (noun, verb) = (solution["noun"] as i32, solution["verb"] as i32);
+
+    // reads through the parameters aren't tracked, so double check the answer
+    if solve_computer(computer, Some((noun, verb))) == Ok(goal) {
+        Some((100 * noun) + verb)
+    } else {
+        None
+    }
+}
+
+fn search(computer: &Computer) -> i32 {
+    let variants = (0..=99)
+        .cartesian_product(0..=99)
+        .map(|(noun, verb)| Variant::patch(&[(1, noun), (2, verb)]));
+    let found = computer
+        .batch()
+        .find(variants, |outcome| outcome.result == Ok(GOAL));
+
+    if let Some(found) = found {
+        let noun = found.variant.patches[0].1;
+        let verb = found.variant.patches[1].1;
         (100 * noun) + verb
     } else {
         unreachable!()
@@ -137,6 +121,17 @@ mod tests {
         assert_eq!(solve_computer(&input, None), Ok(30));
     }
 
+    #[test]
+    fn test_solve_symbolic() {
+        // memory[0] = 100 * noun + verb
+        let input = generator("1,0,0,3,2,1,13,3,1,3,2,0,99,100");
+        assert_eq!(solve_symbolic(&input, 1202), Some(1202));
+
+        // memory[0] = memory[noun] + memory[verb]
+        let input = generator(SAMPLE_2);
+        assert_eq!(solve_symbolic(&input, 2), None);
+    }
+
     // #[test]
     // this example doesn't make sense
     fn _test_part_2() {
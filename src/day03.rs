@@ -27,7 +27,7 @@ fn generator(input: &str) -> Input {
                 //     line
                 // );
 
-                vec![acc, line].concat()
+                [acc, line].concat()
             });
             res
         })
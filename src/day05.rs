@@ -1,11 +1,16 @@
-use crate::computer::Computer;
+use crate::computer::{Computer, Isa};
 
 #[aoc(day5, part1)]
 fn part_1(input: &str) -> i32 {
     let mut computer = Computer::from(input);
+    computer.isa = Isa::Day5;
     computer.input = Some(1);
     computer.run_program().expect("Program failed");
-    computer.check_diagnostics().unwrap()
+    let report = computer.diagnostic_report();
+    match report.result() {
+        Ok(code) => code,
+        Err(report) => panic!("Diagnostics failed!\n{report}"),
+    }
 }
 
 #[aoc(day5, part2)]
@@ -15,6 +20,8 @@ fn part_2(input: &str) -> i32 {
 
 #[cfg(test)]
 mod tests {
+    use crate::computer::dump::MemoryDiff;
+    use crate::computer::testcase::run_cases;
     use crate::computer::{Computer, OpCode, ParameterMode};
 
     #[test]
@@ -48,11 +55,13 @@ mod tests {
         let mut computer = Computer::from("1002,4,3,4,33");
         let res = computer.run_program();
         assert_eq!(res, Ok(1002));
-        assert_eq!(computer.memory, vec![1002, 4, 3, 4, 99]);
+        let diff = MemoryDiff::new(&[1002, 4, 3, 4, 99], &computer.memory);
+        assert!(diff.is_empty(), "memory differs:\n{diff}");
 
         let mut computer = Computer::from("1101,100,-1,4,0");
         computer.run_program().expect("Program failed");
-        assert_eq!(computer.memory, vec![1101, 100, -1, 4, 99]);
+        let diff = MemoryDiff::new(&[1101, 100, -1, 4, 99], &computer.memory);
+        assert!(diff.is_empty(), "memory differs:\n{diff}");
     }
 
     #[test]
@@ -142,4 +151,10 @@ mod tests {
         assert_eq!(Computer::run_from(larger_program, Some(8)), Ok(1000));
         assert_eq!(Computer::run_from(larger_program, Some(9)), Ok(1001));
     }
+
+    #[test]
+    fn test_cases() {
+        let report = run_cases(include_str!("day05.cases")).expect("Invalid case file");
+        assert!(report.is_ok(), "{report}");
+    }
 }
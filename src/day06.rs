@@ -64,14 +64,14 @@ fn part_2(input: &Input) -> usize {
     let you = "YOU".to_string();
     let san = "SAN".to_string();
 
-    let start = root_of(&input, you).unwrap();
-    let goal = root_of(&input, san).unwrap();
+    let start = root_of(input, you).unwrap();
+    let goal = root_of(input, san).unwrap();
 
     bfs(
         start,
         |key| {
             let mut successors = input.get(key).unwrap().clone();
-            if let Some(root) = root_of(&input, key.to_string()) {
+            if let Some(root) = root_of(input, key.to_string()) {
                 successors.push(root.to_string());
             }
             successors